@@ -0,0 +1,64 @@
+pub const USAGE: &str = "Usage: mltop [--refresh-rate <ms>]
+
+Options:
+  -r, --refresh-rate <ms>  Milliseconds between two refreshes of the data
+  -h, --help               Print this help";
+
+/// The command line arguments
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub help: bool,
+    // milliseconds, overrides the one in the config file
+    pub refresh_rate: Option<u64>,
+}
+
+impl Args {
+    /// Parses the arguments, without the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            match name.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "-r" | "--refresh-rate" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or(format!("{} needs a value", name))?;
+                    let rate = value
+                        .parse()
+                        .map_err(|_| format!("invalid refresh rate: {}", value))?;
+                    parsed.refresh_rate = Some(rate);
+                }
+                _ => return Err(format!("unknown argument: {}", name)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Args;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+        assert_eq!(
+            parse(&["--refresh-rate", "500"]).unwrap().refresh_rate,
+            Some(500)
+        );
+        assert_eq!(parse(&["-r=2000"]).unwrap().refresh_rate, Some(2000));
+        assert!(parse(&["--help"]).unwrap().help);
+        assert!(parse(&["--refresh-rate"]).is_err());
+        assert!(parse(&["--refresh-rate", "fast"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+}
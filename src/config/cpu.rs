@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct CpuConfig {
+    // shows the current frequency of each core next to its bar
+    pub show_frequency: bool,
+    // switches to a heatmap of the cores when the grid of bars would be taller than this
+    pub max_grid_height: u16,
+}
+
+impl Default for CpuConfig {
+    fn default() -> Self {
+        CpuConfig {
+            show_frequency: false,
+            max_grid_height: 12,
+        }
+    }
+}
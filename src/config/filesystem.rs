@@ -0,0 +1,31 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct FilesystemConfig {
+    // the paths shown in the filesystem panel, all the mounted filesystems if empty
+    pub paths: Vec<String>,
+    // percentage of used space above which we warn about a filesystem, 0 to never warn
+    pub warning_threshold: f32,
+}
+
+impl Default for FilesystemConfig {
+    fn default() -> Self {
+        FilesystemConfig {
+            paths: Vec::new(),
+            warning_threshold: 90.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilesystemConfig;
+
+    #[test]
+    fn test_parse_filesystem() {
+        let config: FilesystemConfig = toml::from_str(r#"paths = ["/data", "/scratch"]"#).unwrap();
+        assert_eq!(config.paths, ["/data", "/scratch"]);
+        assert_eq!(config.warning_threshold, 90.0);
+    }
+}
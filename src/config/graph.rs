@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Default, Deserialize, Debug)]
+#[serde(default)]
+pub struct GraphConfig {
+    // adds the utilization of the busiest disk to the line graph
+    pub show_disk: bool,
+    // adds the utilization of the busiest network interface, relative to its link speed
+    pub show_network: bool,
+}
@@ -0,0 +1,31 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct MemoryLeakConfig {
+    // seconds of resident memory history a process needs to be flagged as leaking
+    pub window: u64,
+    // the slowest growth that counts as a leak
+    pub min_growth_mib_per_hour: f64,
+}
+
+impl Default for MemoryLeakConfig {
+    fn default() -> Self {
+        MemoryLeakConfig {
+            window: 600,
+            min_growth_mib_per_hour: 100.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryLeakConfig;
+
+    #[test]
+    fn test_parse_memory_leak() {
+        let config: MemoryLeakConfig = toml::from_str("window = 1800").unwrap();
+        assert_eq!(config.window, 1800);
+        assert_eq!(config.min_growth_mib_per_hour, 100.0);
+    }
+}
@@ -3,22 +3,60 @@ use std::env::home_dir;
 use std::fs::read_to_string;
 use std::sync::OnceLock;
 
+pub mod cpu;
+pub mod filesystem;
+pub mod graph;
+pub mod memory_leak;
+pub mod process_table;
 pub mod theme;
 
+pub use cpu::CpuConfig;
+pub use filesystem::FilesystemConfig;
+pub use graph::GraphConfig;
+pub use memory_leak::MemoryLeakConfig;
+pub use process_table::ProcessTableConfig;
 pub use theme::Theme;
 
 pub const REFRESH_RATE_MILLIS: u64 = 1000;
-pub const GRAPH_X_AXIS_WINDOW_IN_SECONDS: usize = 120;
-pub const MAX_STORED_SNAPSHOTS: usize = 300;
+// sysinfo needs at least 200ms between two refreshes to compute the cpu usage
+pub const MIN_REFRESH_RATE_MILLIS: u64 = 250;
+// the refresh rates `+` and `-` step through
+pub const REFRESH_RATES_MILLIS: [u64; 7] = [250, 500, 1000, 2000, 3000, 5000, 10000];
+pub const GRAPH_X_AXIS_WINDOW_IN_SECONDS: u64 = 120;
+pub const MAX_STORED_PROCESS_SNAPSHOTS: usize = 60;
+// refreshes in a row a GPU process must run off the GPU's NUMA node to be highlighted
+pub const NUMA_MISMATCH_SAMPLES: usize = 5;
 pub const MESSAGE_EXPIRATION_IN_SECONDS: u64 = 10;
 
 const DEFAULT_CONFIG_FILE: &str = ".config/mltop/config.toml";
 
 pub static CONFIG: OnceLock<Config> = OnceLock::new();
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Deserialize, Debug)]
+#[serde(default)]
 pub struct Config {
+    // milliseconds between two refreshes of the data
+    pub refresh_rate: u64,
     pub theme: Theme,
+    pub process_table: ProcessTableConfig,
+    pub cpu: CpuConfig,
+    pub graph: GraphConfig,
+    pub filesystem: FilesystemConfig,
+    pub memory_leak: MemoryLeakConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            refresh_rate: REFRESH_RATE_MILLIS,
+            theme: Theme::default(),
+            process_table: ProcessTableConfig::default(),
+            cpu: CpuConfig::default(),
+            graph: GraphConfig::default(),
+            filesystem: FilesystemConfig::default(),
+            memory_leak: MemoryLeakConfig::default(),
+        }
+    }
 }
 
 impl Config {
@@ -40,11 +78,9 @@ impl Config {
     }
 }
 
+/// Reads the config, unless it was already read, e.g. by another test
 pub fn init_config() {
-    let config = Config::get();
-    CONFIG
-        .set(config)
-        .expect("config should be initialized only once");
+    CONFIG.get_or_init(Config::get);
 }
 
 pub fn get_config() -> &'static Config {
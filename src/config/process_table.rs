@@ -0,0 +1,202 @@
+use ratatui::layout::{Alignment, Constraint};
+use serde::Deserialize;
+
+/// A column of the process table
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessColumn {
+    Pid,
+    User,
+    Type,
+    Nice,
+    Affinity,
+    Cpu,
+    Mem,
+    Memory,
+    Growth,
+    GpuMemory,
+    Read,
+    Write,
+    State,
+    StartTime,
+    Threads,
+    Framework,
+    Container,
+    CpuHistory,
+    GpuMemoryHistory,
+    Command,
+}
+
+impl ProcessColumn {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ProcessColumn::Pid => "PID",
+            ProcessColumn::User => "USER",
+            ProcessColumn::Type => "TYPE",
+            ProcessColumn::Nice => "NI",
+            ProcessColumn::Affinity => "CPUS",
+            ProcessColumn::Cpu => "CPU%",
+            ProcessColumn::Mem => "MEM%",
+            ProcessColumn::Memory => "MEMORY",
+            ProcessColumn::Growth => "GROWTH",
+            ProcessColumn::GpuMemory => "GPU MEM",
+            ProcessColumn::Read => "READ/s",
+            ProcessColumn::Write => "WRITE/s",
+            ProcessColumn::State => "S",
+            ProcessColumn::StartTime => "START",
+            ProcessColumn::Threads => "THR",
+            ProcessColumn::Framework => "FRAMEWORK",
+            ProcessColumn::Container => "CONTAINER",
+            ProcessColumn::CpuHistory => "CPU HIST",
+            ProcessColumn::GpuMemoryHistory => "GPU MEM HIST",
+            ProcessColumn::Command => "Command",
+        }
+    }
+
+    pub fn default_width(&self) -> u16 {
+        match self {
+            ProcessColumn::Pid => 7,
+            ProcessColumn::User => 10,
+            ProcessColumn::Type => 8,
+            ProcessColumn::Nice => 3,
+            ProcessColumn::Affinity => 7,
+            ProcessColumn::Cpu => 6,
+            ProcessColumn::Mem => 6,
+            ProcessColumn::Memory => 9,
+            ProcessColumn::Growth => 8,
+            ProcessColumn::GpuMemory => 9,
+            ProcessColumn::Read => 7,
+            ProcessColumn::Write => 8,
+            ProcessColumn::State => 2,
+            ProcessColumn::StartTime => 6,
+            ProcessColumn::Threads => 4,
+            ProcessColumn::Framework => 10,
+            ProcessColumn::Container => 20,
+            ProcessColumn::CpuHistory => 12,
+            ProcessColumn::GpuMemoryHistory => 12,
+            ProcessColumn::Command => 10,
+        }
+    }
+
+    pub fn alignment(&self) -> Alignment {
+        match self {
+            ProcessColumn::User
+            | ProcessColumn::Type
+            | ProcessColumn::Affinity
+            | ProcessColumn::State
+            | ProcessColumn::Framework
+            | ProcessColumn::Container
+            | ProcessColumn::CpuHistory
+            | ProcessColumn::GpuMemoryHistory
+            | ProcessColumn::Command => Alignment::Left,
+            _ => Alignment::Right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnConfig {
+    // `"user"`
+    Name(ProcessColumn),
+    // `{ name = "user", width = 12 }`
+    Detailed {
+        name: ProcessColumn,
+        width: Option<u16>,
+    },
+}
+
+impl ColumnConfig {
+    pub fn column(&self) -> ProcessColumn {
+        match self {
+            ColumnConfig::Name(c) => *c,
+            ColumnConfig::Detailed { name, .. } => *name,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        match self {
+            ColumnConfig::Detailed { width: Some(w), .. } => *w,
+            _ => self.column().default_width(),
+        }
+    }
+
+    pub fn constraint(&self) -> Constraint {
+        match self.column() {
+            // the command takes up all the remaining space
+            ProcessColumn::Command => Constraint::Min(self.width()),
+            _ => Constraint::Length(self.width()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ProcessTableConfig {
+    pub columns: Vec<ColumnConfig>,
+    // processes whose command contains any of these are always pinned
+    pub pinned: Vec<String>,
+}
+
+impl ProcessTableConfig {
+    /// Whether a process running `command` is pinned by one of the `pinned` patterns
+    pub fn pins(&self, command: &str) -> bool {
+        self.pinned
+            .iter()
+            .any(|pattern| command.contains(pattern.as_str()))
+    }
+}
+
+impl Default for ProcessTableConfig {
+    fn default() -> Self {
+        ProcessTableConfig {
+            columns: [
+                ProcessColumn::Pid,
+                ProcessColumn::User,
+                ProcessColumn::Type,
+                ProcessColumn::Nice,
+                ProcessColumn::Affinity,
+                ProcessColumn::Cpu,
+                ProcessColumn::Mem,
+                ProcessColumn::Memory,
+                ProcessColumn::Growth,
+                ProcessColumn::Threads,
+                ProcessColumn::Command,
+            ]
+            .into_iter()
+            .map(ColumnConfig::Name)
+            .collect(),
+            pinned: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProcessColumn, ProcessTableConfig};
+    use ratatui::layout::Constraint;
+
+    #[test]
+    fn test_parse_columns() {
+        let config: ProcessTableConfig = toml::from_str(
+            r#"
+                columns = ["pid", { name = "user", width = 12 }, "gpu_memory", "command"]
+                pinned = ["train.py"]
+            "#,
+        )
+        .unwrap();
+        let columns: Vec<ProcessColumn> = config.columns.iter().map(|c| c.column()).collect();
+        assert_eq!(
+            columns,
+            [
+                ProcessColumn::Pid,
+                ProcessColumn::User,
+                ProcessColumn::GpuMemory,
+                ProcessColumn::Command
+            ]
+        );
+        assert_eq!(config.columns[1].constraint(), Constraint::Length(12));
+        assert_eq!(config.columns[3].constraint(), Constraint::Min(10));
+        assert_eq!(config.pinned, ["train.py"]);
+    }
+}
@@ -8,6 +8,8 @@ pub struct Theme {
     pub line_graph_mem: Color,
     pub line_graph_gpu_use: Color,
     pub line_graph_gpu_mem: Color,
+    pub line_graph_disk: Color,
+    pub line_graph_network: Color,
 
     pub bar_low_use: Color,
     pub bar_medium_use: Color,
@@ -15,6 +17,19 @@ pub struct Theme {
     pub bar_high_use: Color,
     pub bar_text: Color,
 
+    pub cpu_user: Color,
+    pub cpu_nice: Color,
+    pub cpu_system: Color,
+    pub cpu_iowait: Color,
+    pub cpu_irq: Color,
+    pub cpu_softirq: Color,
+    pub cpu_steal: Color,
+
+    pub memory_used: Color,
+    pub memory_buffers: Color,
+    pub memory_shared: Color,
+    pub memory_cache: Color,
+
     pub processes_header_fg: Color,
     pub processes_header_bg: Color,
     pub processes_cpu: Color,
@@ -24,6 +39,13 @@ pub struct Theme {
     pub processes_bin_name: Color,
     pub processes_selected_fg: Color,
     pub processes_selected_bg: Color,
+    pub processes_pinned: Color,
+    pub processes_numa_mismatch: Color,
+    pub processes_leak: Color,
+
+    pub panel_header_fg: Color,
+    pub panel_header_bg: Color,
+    pub panel_text: Color,
 
     pub action_bar_msg_bg: Color,
     pub action_bar_msg_fg: Color,
@@ -40,6 +62,8 @@ impl Default for Theme {
             line_graph_mem: Color::Green,
             line_graph_gpu_use: Color::Blue,
             line_graph_gpu_mem: Color::Yellow,
+            line_graph_disk: Color::Magenta,
+            line_graph_network: Color::Cyan,
 
             bar_low_use: Color::Green,
             bar_medium_use: Color::Yellow,
@@ -47,6 +71,20 @@ impl Default for Theme {
             bar_high_use: Color::Red,
             bar_text: Color::DarkGray,
 
+            // same as htop
+            cpu_user: Color::Green,
+            cpu_nice: Color::Blue,
+            cpu_system: Color::Red,
+            cpu_iowait: Color::DarkGray,
+            cpu_irq: Color::Yellow,
+            cpu_softirq: Color::Magenta,
+            cpu_steal: Color::Cyan,
+
+            memory_used: Color::Green,
+            memory_buffers: Color::Blue,
+            memory_shared: Color::Magenta,
+            memory_cache: Color::Yellow,
+
             processes_header_fg: Color::Black,
             processes_header_bg: Color::Green,
             processes_cpu: Color::White,
@@ -56,6 +94,13 @@ impl Default for Theme {
             processes_bin_name: Color::Magenta,
             processes_selected_fg: Color::Black,
             processes_selected_bg: Color::Cyan,
+            processes_pinned: Color::Yellow,
+            processes_numa_mismatch: Color::Red,
+            processes_leak: Color::LightRed,
+
+            panel_header_fg: Color::Black,
+            panel_header_bg: Color::Green,
+            panel_text: Color::White,
 
             action_bar_msg_bg: Color::Red,
             action_bar_msg_fg: Color::Black,
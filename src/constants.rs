@@ -1,3 +1,6 @@
+pub const BYTES_PER_KB: u64 = 1024;
 pub const BYTES_PER_MB: u64 = 1024_u64.pow(2);
 pub const BYTES_PER_GB: u64 = BYTES_PER_MB * 1024;
 pub const BYTES_PER_GB_FLOAT: f32 = BYTES_PER_GB as f32;
+pub const SYSFS_ROOT: &str = "/sys";
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::fs::read_to_string;
+use std::path::Path;
+
+use super::processes::Process;
+
+// length of the shortened container ids, like `docker ps` shows them
+const SHORT_ID_LENGTH: usize = 12;
+
+// (prefix, runtime) of the systemd scopes container runtimes create, e.g. `docker-<id>.scope`
+const CONTAINER_SCOPES: [(&str, &str); 4] = [
+    ("docker-", "docker"),
+    ("libpod-", "podman"),
+    ("cri-containerd-", "containerd"),
+    ("crio-", "crio"),
+];
+
+/// What a process belongs to, according to its cgroup
+#[derive(Clone, Debug, PartialEq)]
+pub enum CgroupOwner {
+    Container { runtime: &'static str, id: String },
+    SlurmJob(String),
+    SystemdUnit(String),
+}
+
+impl Display for CgroupOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CgroupOwner::Container { runtime, id } => {
+                write!(f, "{}:{}", runtime, &id[..id.len().min(SHORT_ID_LENGTH)])
+            }
+            CgroupOwner::SlurmJob(job) => write!(f, "slurm:{}", job),
+            CgroupOwner::SystemdUnit(unit) => write!(f, "{}", unit),
+        }
+    }
+}
+
+impl CgroupOwner {
+    pub fn read(pid: u32) -> Option<CgroupOwner> {
+        Self::read_from(Path::new(&format!("/proc/{}/cgroup", pid)))
+    }
+
+    pub fn read_from(path: &Path) -> Option<CgroupOwner> {
+        Self::parse(&read_to_string(path).ok()?)
+    }
+
+    /// Parses the contents of a `/proc/<pid>/cgroup` file
+    pub fn parse(contents: &str) -> Option<CgroupOwner> {
+        Self::from_path(cgroup_path(contents)?)
+    }
+
+    /// Finds the owner from a cgroup path, e.g. `/system.slice/docker-<id>.scope`
+    pub fn from_path(path: &str) -> Option<CgroupOwner> {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+
+        // cgroup v2 and systemd v1: container scopes, e.g. `docker-<id>.scope`
+        for component in components.iter().rev() {
+            let Some(name) = component.strip_suffix(".scope") else {
+                continue;
+            };
+            for (prefix, runtime) in CONTAINER_SCOPES {
+                if let Some(id) = name.strip_prefix(prefix).filter(|id| is_container_id(id)) {
+                    return Some(CgroupOwner::Container {
+                        runtime,
+                        id: id.to_string(),
+                    });
+                }
+            }
+        }
+
+        // cgroupfs v1: `/docker/<id>` or `/kubepods/<qos>/pod<uid>/<id>`
+        if let Some(id) = components.iter().rev().find(|c| is_container_id(c)) {
+            let runtime = match components.first() {
+                Some(&"docker") => "docker",
+                Some(c) if c.starts_with("kubepods") => "k8s",
+                _ => "container",
+            };
+            return Some(CgroupOwner::Container {
+                runtime,
+                id: id.to_string(),
+            });
+        }
+
+        if let Some(job) = components.iter().find_map(|c| c.strip_prefix("job_")) {
+            return Some(CgroupOwner::SlurmJob(job.to_string()));
+        }
+
+        components
+            .iter()
+            .rev()
+            .find(|c| c.ends_with(".service") || c.ends_with(".scope"))
+            .map(|unit| CgroupOwner::SystemdUnit(unit.to_string()))
+    }
+}
+
+/// The (controllers, path) of each hierarchy of a `/proc/<pid>/cgroup` file.
+/// Remembers the owner of every process, since processes practically never move to
+/// another cgroup, so `/proc/<pid>/cgroup` is only read once per process
+#[derive(Default)]
+pub struct CgroupOwnerCache {
+    // keyed by (pid, start time) to not get confused by reused pids
+    owners: HashMap<(u32, u64), Option<CgroupOwner>>,
+}
+
+impl CgroupOwnerCache {
+    /// Sets the owner of every process and forgets the processes that exited.
+    /// Threads share the cgroup of their process, so they get its owner.
+    pub fn annotate(&mut self, processes: &mut [Process]) {
+        let mut alive = HashSet::new();
+        for p in processes.iter_mut().filter(|p| !p.is_thread()) {
+            let key = (p.pid, p.start_time);
+            alive.insert(key);
+            p.container = self
+                .owners
+                .entry(key)
+                .or_insert_with(|| CgroupOwner::read(p.pid))
+                .clone();
+        }
+        self.owners.retain(|key, _| alive.contains(key));
+
+        let owners: HashMap<u32, Option<CgroupOwner>> = processes
+            .iter()
+            .filter(|p| !p.is_thread())
+            .map(|p| (p.pid, p.container.clone()))
+            .collect();
+        for thread in processes.iter_mut().filter(|p| p.is_thread()) {
+            thread.container = thread.owner.and_then(|o| owners.get(&o).cloned().flatten());
+        }
+    }
+}
+
+/// The controllers are empty for the unified (v2) hierarchy.
+pub fn hierarchies(contents: &str) -> Vec<(&str, &str)> {
+    contents
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.splitn(3, ':');
+            let (_, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+            Some((controllers, path))
+        })
+        .collect()
+}
+
+/// Picks the path of the unified (v2) hierarchy, or the systemd one on v1 systems
+pub fn cgroup_path(contents: &str) -> Option<&str> {
+    let hierarchies = hierarchies(contents);
+
+    // hybrid v1 systems also list an (often unused) v2 hierarchy, so we prefer
+    // the v1 systemd one when there are any v1 controllers
+    let v1 = hierarchies.iter().find(|(c, _)| *c == "name=systemd");
+    let v2 = hierarchies.iter().find(|(c, _)| c.is_empty());
+    v1.or(v2).or(hierarchies.first()).map(|(_, path)| *path)
+}
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CgroupOwner;
+    use std::path::PathBuf;
+
+    fn read_fixture(name: &str) -> Option<CgroupOwner> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/cgroup")
+            .join(name);
+        assert!(path.is_file(), "missing fixture {:?}", path);
+        CgroupOwner::read_from(&path)
+    }
+
+    fn container(runtime: &'static str, id: &str) -> Option<CgroupOwner> {
+        Some(CgroupOwner::Container {
+            runtime,
+            id: id.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_containers() {
+        assert_eq!(
+            read_fixture("docker_v2.txt"),
+            container(
+                "docker",
+                "3f4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b2a3f4e"
+            )
+        );
+        assert_eq!(
+            read_fixture("docker_v1.txt"),
+            container(
+                "docker",
+                "8d3c1e0b2a4f5e6d7c8b9a0f1e2d3c4b5a6f7e8d9c0b1a2f3e4d5c6b7a8f9e0d"
+            )
+        );
+        assert_eq!(
+            read_fixture("podman.txt"),
+            container(
+                "podman",
+                "a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2"
+            )
+        );
+        assert_eq!(
+            read_fixture("kubernetes.txt"),
+            container(
+                "containerd",
+                "5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f"
+            )
+        );
+        assert_eq!(
+            read_fixture("docker_v2.txt").unwrap().to_string(),
+            "docker:3f4e5d6c7b8a"
+        );
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(
+            read_fixture("systemd.txt"),
+            Some(CgroupOwner::SystemdUnit(String::from("session-4.scope")))
+        );
+        assert_eq!(
+            read_fixture("slurm.txt"),
+            Some(CgroupOwner::SlurmJob(String::from("48213")))
+        );
+        assert_eq!(read_fixture("host.txt"), None);
+    }
+}
@@ -0,0 +1,331 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use super::cgroup::hierarchies;
+use super::clock::RateClock;
+use super::topology::online_cpus;
+use crate::constants::{CGROUP_ROOT, SYSFS_ROOT};
+use crate::utils::parse_cpu_list;
+
+// cgroup v1 shows "no memory limit" as the largest page aligned i64
+const V1_UNLIMITED: u64 = 1 << 62;
+
+/// The limits of the cgroup mltop runs in, e.g. of its container or Slurm job, with
+/// the usage of the cgroup that sets them. Limits set on a parent cgroup count too.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CgroupLimits {
+    // bytes
+    pub memory_max: Option<u64>,
+    pub memory_used: u64,
+    // in cpus, e.g. 1.5 for a quota of 150ms every 100ms
+    pub cpu_quota: Option<f32>,
+    // the cpus the cgroup can run on
+    pub cpuset: Option<Vec<usize>>,
+    // cpus used by the cgroup, `None` on the first refresh
+    pub cpu_usage: Option<f32>,
+}
+
+impl CgroupLimits {
+    /// The number of cpus the cgroup can use at most, out of the `cpus` of the
+    /// machine. `None` if it can use all of them.
+    pub fn cpu_limit(&self, cpus: usize) -> Option<f32> {
+        let cpuset = self
+            .cpuset
+            .as_ref()
+            .map(|c| c.len())
+            .filter(|c| *c < cpus)
+            .map(|c| c as f32);
+        match (self.cpu_quota, cpuset) {
+            (Some(q), Some(c)) => Some(q.min(c)),
+            (q, c) => q.or(c),
+        }
+    }
+
+    /// Whether the cgroup can run on `cpu`
+    pub fn allows_cpu(&self, cpu: usize) -> bool {
+        self.cpuset.as_ref().is_none_or(|c| c.contains(&cpu))
+    }
+}
+
+/// Reads the limits of a cgroup, and measures its cpu usage between refreshes
+pub struct CgroupTracker {
+    v2: bool,
+    // the directories of the cgroup and of its parents, for each controller
+    memory: Vec<PathBuf>,
+    cpu: Vec<PathBuf>,
+    cpuset: Vec<PathBuf>,
+    // a cpuset with all of them isn't a limit, like the one of the root cgroup
+    online_cpus: Option<Vec<usize>>,
+    // µs of cpu time
+    previous_usage: Option<u64>,
+    clock: RateClock,
+}
+
+impl CgroupTracker {
+    /// The tracker of mltop's own cgroup
+    pub fn own() -> CgroupTracker {
+        Self::new(
+            Path::new("/proc/self/cgroup"),
+            Path::new(CGROUP_ROOT),
+            Path::new(SYSFS_ROOT),
+        )
+    }
+
+    /// The tracker of the cgroup in `cgroup_file` (a `/proc/<pid>/cgroup` file),
+    /// with the cgroup filesystem mounted at `root`
+    pub fn new(cgroup_file: &Path, root: &Path, sysfs: &Path) -> CgroupTracker {
+        let online_cpus = online_cpus(sysfs);
+        let contents = read_to_string(cgroup_file).unwrap_or_default();
+        let hierarchies = hierarchies(&contents);
+        let v1 = |controller: &str| {
+            hierarchies
+                .iter()
+                .find(|(c, _)| c.split(',').any(|c| c == controller))
+                .map(|(c, path)| ancestors(&root.join(c), path))
+                .unwrap_or_default()
+        };
+        let (memory, cpu, cpuset) = (v1("memory"), v1("cpu"), v1("cpuset"));
+        let v2 = memory.is_empty() && cpu.is_empty() && cpuset.is_empty();
+        match v2 {
+            true => {
+                let dirs = hierarchies
+                    .iter()
+                    .find(|(c, _)| c.is_empty())
+                    .map(|(_, path)| ancestors(root, path))
+                    .unwrap_or_default();
+                CgroupTracker {
+                    v2,
+                    memory: dirs.clone(),
+                    cpu: dirs.clone(),
+                    cpuset: dirs,
+                    online_cpus,
+                    previous_usage: None,
+                    clock: RateClock::default(),
+                }
+            }
+            false => CgroupTracker {
+                v2,
+                memory,
+                cpu,
+                cpuset,
+                online_cpus,
+                previous_usage: None,
+                clock: RateClock::default(),
+            },
+        }
+    }
+
+    /// `None` if the cgroup has no limits, or there's no cgroup filesystem
+    pub fn measure(&mut self) -> Option<CgroupLimits> {
+        let elapsed = self.clock.elapsed();
+        let quota = self.cpu_quota();
+        let cpu_time = self.cpu_time(quota.map(|(_, dir)| dir));
+        let limits = self.limits_with(quota.map(|(q, _)| q));
+        let cpu_usage = self.update(cpu_time, elapsed);
+        Some(CgroupLimits {
+            cpu_usage,
+            ..limits?
+        })
+    }
+
+    /// The cpus used since the previous `cpu_time` (µs), `elapsed` seconds ago
+    pub fn update(&mut self, cpu_time: Option<u64>, elapsed: Option<f64>) -> Option<f32> {
+        let previous = std::mem::replace(&mut self.previous_usage, cpu_time);
+        let elapsed = elapsed.filter(|e| *e > 0.0)?;
+        let used = cpu_time?.saturating_sub(previous?) as f64 / 1_000_000.0;
+        Some((used / elapsed) as f32)
+    }
+
+    /// The limits, without the cpu usage
+    pub fn limits(&self) -> Option<CgroupLimits> {
+        self.limits_with(self.cpu_quota().map(|(q, _)| q))
+    }
+
+    // the limits, with the `cpu_quota` already read
+    fn limits_with(&self, cpu_quota: Option<f32>) -> Option<CgroupLimits> {
+        let (memory_max, memory_dir) = match self.memory_max() {
+            Some((max, dir)) => (Some(max), Some(dir)),
+            None => (None, self.memory.first()),
+        };
+        let current = self.file("memory.current", "memory.usage_in_bytes");
+        let memory_used = memory_dir
+            .and_then(|d| read(d, current))
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(0);
+        let effective = self.file("cpuset.cpus.effective", "cpuset.effective_cpus");
+        let cpuset = self
+            .cpuset
+            .iter()
+            .find_map(|d| read(d, effective).or_else(|| read(d, "cpuset.cpus")))
+            .and_then(|c| parse_cpu_list(&c))
+            .filter(|c| !c.is_empty() && Some(c) != self.online_cpus.as_ref());
+
+        if memory_max.is_none() && cpu_quota.is_none() && cpuset.is_none() {
+            return None;
+        }
+        Some(CgroupLimits {
+            memory_max,
+            memory_used,
+            cpu_quota,
+            cpuset,
+            cpu_usage: None,
+        })
+    }
+
+    // the name of a file in cgroup v2, or in v1
+    fn file(&self, v2: &'static str, v1: &'static str) -> &'static str {
+        match self.v2 {
+            true => v2,
+            false => v1,
+        }
+    }
+
+    // the lowest memory limit, with the directory of the cgroup that sets it
+    fn memory_max(&self) -> Option<(u64, &PathBuf)> {
+        let max = self.file("memory.max", "memory.limit_in_bytes");
+        self.memory
+            .iter()
+            .filter_map(|d| {
+                // "max" in v2 when there's no limit
+                let max: u64 = read(d, max)?.parse().ok()?;
+                (max < V1_UNLIMITED).then_some((max, d))
+            })
+            .min_by_key(|(max, _)| *max)
+    }
+
+    // the lowest cpu quota, with the directory of the cgroup that sets it
+    fn cpu_quota(&self) -> Option<(f32, &PathBuf)> {
+        self.cpu
+            .iter()
+            .filter_map(|d| {
+                let quota = match self.v2 {
+                    true => parse_cpu_max(&read(d, "cpu.max")?)?,
+                    false => {
+                        // -1 when there's no quota
+                        let quota: i64 = read(d, "cpu.cfs_quota_us")?.parse().ok()?;
+                        let period: i64 = read(d, "cpu.cfs_period_us")?.parse().ok()?;
+                        (quota > 0 && period > 0).then(|| quota as f32 / period as f32)?
+                    }
+                };
+                Some((quota, d))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+    }
+
+    // µs of cpu time used by the cgroup that sets the quota, in `quota_dir`, or by our own
+    fn cpu_time(&self, quota_dir: Option<&PathBuf>) -> Option<u64> {
+        let dir = match quota_dir {
+            Some(dir) => dir,
+            None => self.cpu.first()?,
+        };
+        match self.v2 {
+            true => read(dir, "cpu.stat")?
+                .lines()
+                .find_map(|l| l.strip_prefix("usage_usec "))?
+                .trim()
+                .parse()
+                .ok(),
+            // in ns
+            false => Some(read(dir, "cpuacct.usage")?.parse::<u64>().ok()? / 1000),
+        }
+    }
+}
+
+// the existing directories of a cgroup and of its parents, starting with its own.
+// Inside a container the cgroup filesystem often starts at the container's cgroup,
+// so its path from /proc doesn't exist and the root is the container's cgroup.
+fn ancestors(root: &Path, path: &str) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let mut path = Some(Path::new(path.trim_start_matches('/')));
+    while let Some(p) = path {
+        let dir = root.join(p);
+        if dir.is_dir() {
+            dirs.push(dir);
+        }
+        path = p.parent();
+    }
+    dirs
+}
+
+fn read(dir: &Path, file: &str) -> Option<String> {
+    read_to_string(dir.join(file))
+        .ok()
+        .map(|c| c.trim().to_string())
+}
+
+/// Parses a cgroup v2 `cpu.max`, e.g. `150000 100000`, into cpus.
+/// `None` when there's no quota (`max 100000`).
+fn parse_cpu_max(contents: &str) -> Option<f32> {
+    let (quota, period) = contents.trim().split_once(' ')?;
+    let quota: f32 = quota.parse().ok()?;
+    let period: f32 = period.parse().ok()?;
+    (period > 0.0).then_some(quota / period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cpu_max, CgroupLimits, CgroupTracker};
+    use crate::constants::BYTES_PER_GB;
+    use std::path::PathBuf;
+
+    fn tracker(cgroup: &str, root: &str) -> CgroupTracker {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let (cgroup, root) = (fixtures.join("cgroup").join(cgroup), fixtures.join(root));
+        assert!(cgroup.is_file(), "missing fixture {:?}", cgroup);
+        assert!(root.is_dir(), "missing fixture {:?}", root);
+        // 8 cpus online
+        CgroupTracker::new(&cgroup, &root, &fixtures.join("sysfs/amd"))
+    }
+
+    #[test]
+    fn test_parse_cpu_max() {
+        assert_eq!(parse_cpu_max("150000 100000\n"), Some(1.5));
+        assert_eq!(parse_cpu_max("max 100000"), None);
+    }
+
+    #[test]
+    fn test_v2_limits() {
+        // the job sets the limits, the task inside it the cpuset
+        let limits = tracker("slurm.txt", "cgroupfs/v2").limits().unwrap();
+        assert_eq!(limits.memory_max, Some(64 * BYTES_PER_GB));
+        assert_eq!(limits.memory_used, 56 * BYTES_PER_GB);
+        assert_eq!(limits.cpu_quota, Some(6.0));
+        assert_eq!(limits.cpuset, Some(vec![0, 1, 2, 3, 32, 33, 34, 35]));
+        assert_eq!(limits.cpu_limit(64), Some(6.0));
+        assert!(!limits.allows_cpu(4));
+
+        assert_eq!(tracker("host.txt", "cgroupfs/v2").limits(), None);
+    }
+
+    #[test]
+    fn test_root_cpuset() {
+        // the root cgroup has every online cpu in its cpuset, which isn't a limit
+        assert_eq!(tracker("host.txt", "cgroupfs/v2_root").limits(), None);
+    }
+
+    #[test]
+    fn test_v1_limits() {
+        // inside the container, the cgroup filesystem starts at the container's cgroup
+        let mut tracker = tracker("docker_v1.txt", "cgroupfs/v1");
+        let limits = tracker.limits().unwrap();
+        assert_eq!(limits.memory_max, Some(16 * BYTES_PER_GB));
+        assert_eq!(limits.memory_used, 4 * BYTES_PER_GB);
+        assert_eq!(limits.cpu_quota, Some(2.0));
+        assert_eq!(limits.cpuset, None);
+        let quota_dir = tracker.cpu_quota().map(|(_, dir)| dir.clone());
+        assert_eq!(tracker.cpu_time(quota_dir.as_ref()), Some(5_000_000));
+
+        assert_eq!(tracker.update(Some(5_000_000), None), None);
+        assert_eq!(tracker.update(Some(8_000_000), Some(2.0)), Some(1.5));
+    }
+
+    #[test]
+    fn test_cpu_limit() {
+        let limits = CgroupLimits {
+            cpuset: Some((0..64).collect()),
+            ..Default::default()
+        };
+        assert_eq!(limits.cpu_limit(64), None);
+        assert_eq!(limits.cpu_limit(128), Some(64.0));
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Instant;
+
+/// Remembers when a tracker last read its counters, to turn their deltas into rates.
+///
+/// The trackers take the seconds it returns as the `elapsed` of their `update`, where
+/// `None` means it's the first read, so there's no rate yet.
+#[derive(Default)]
+pub struct RateClock {
+    read_at: Option<Instant>,
+}
+
+impl RateClock {
+    /// The seconds since the previous call, `None` on the first one
+    pub fn elapsed(&mut self) -> Option<f64> {
+        self.elapsed_at(Instant::now())
+    }
+
+    pub fn elapsed_at(&mut self, now: Instant) -> Option<f64> {
+        let previous = self.read_at.replace(now);
+        previous.map(|t| now.duration_since(t).as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateClock;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_elapsed() {
+        let mut clock = RateClock::default();
+        let start = Instant::now();
+        assert_eq!(clock.elapsed_at(start), None);
+        assert_eq!(clock.elapsed_at(start + Duration::from_secs(2)), Some(2.0));
+        assert_eq!(clock.elapsed_at(start + Duration::from_secs(3)), Some(1.0));
+    }
+}
@@ -0,0 +1,226 @@
+use std::ops::Range;
+
+// python modules that launch another script, e.g. `python -m torch.distributed.run train.py`
+const LAUNCHER_MODULES: [&str; 5] = [
+    "torch.distributed.run",
+    "torch.distributed.launch",
+    "accelerate.commands.launch",
+    "deepspeed.launcher.runner",
+    "deepspeed",
+];
+
+// options of `conda run` and `uv run` that take a value as the next argument
+const RUNNER_VALUE_OPTIONS: [&str; 16] = [
+    "-n",
+    "--name",
+    "-p",
+    "--prefix",
+    "--cwd",
+    "--python",
+    "--with",
+    "--with-requirements",
+    "--project",
+    "--directory",
+    "--package",
+    "--extra",
+    "--group",
+    "--env-file",
+    "--index",
+    "--index-url",
+];
+
+// options of the python interpreter that take a value as the next argument
+const PYTHON_VALUE_OPTIONS: [&str; 3] = ["-W", "-X", "--check-hash-based-pycs"];
+
+/// Returns the byte range of the part of `cmd` that identifies what's being run.
+///
+/// Usually this is the name of the binary, but for interpreter launches such as
+/// `python -m torch.distributed.run --nproc_per_node 8 train.py --lr 1e-4`, it's the
+/// script or module being run (`train.py`).
+pub fn target_range(cmd: &str) -> Range<usize> {
+    let tokens = tokenize(cmd);
+    if tokens.is_empty() {
+        return 0..0;
+    }
+    find_target(&tokens, 0).unwrap_or_else(|| basename_range(tokens[0]))
+}
+
+/// Whether `cmd` runs an interpreter or launcher, whose actual workload is an argument
+pub fn is_interpreter_launch(cmd: &str) -> bool {
+    let bin = basename(cmd.split(' ').next().unwrap_or(""));
+    is_python(bin)
+        || matches!(
+            bin,
+            "conda" | "mamba" | "micromamba" | "uv" | "accelerate" | "torchrun" | "deepspeed"
+        )
+}
+
+// (byte offset, token) pairs of the whitespace separated tokens of `cmd`
+fn tokenize(cmd: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    for token in cmd.split(' ') {
+        if !token.is_empty() {
+            tokens.push((offset, token));
+        }
+        offset += token.len() + 1;
+    }
+    tokens
+}
+
+fn basename(token: &str) -> &str {
+    token.rsplit('/').next().unwrap_or(token)
+}
+
+fn basename_range((offset, token): (usize, &str)) -> Range<usize> {
+    let start = token.rfind('/').map(|i| i + 1).unwrap_or(0);
+    offset + start..offset + token.len()
+}
+
+fn token_range((offset, token): (usize, &str)) -> Range<usize> {
+    offset..offset + token.len()
+}
+
+fn is_python(bin: &str) -> bool {
+    bin == "python"
+        || bin
+            .strip_prefix("python")
+            .is_some_and(|v| v.starts_with(['2', '3']))
+}
+
+fn find_target(tokens: &[(usize, &str)], i: usize) -> Option<Range<usize>> {
+    let bin = basename(tokens.get(i)?.1);
+    let subcommand = tokens.get(i + 1).map(|t| t.1);
+    match (bin, subcommand) {
+        (b, _) if is_python(b) => find_python_target(tokens, i + 1),
+        ("conda" | "mamba" | "micromamba" | "uv", Some("run")) => {
+            let j = skip_options(tokens, i + 2, &RUNNER_VALUE_OPTIONS);
+            find_target(tokens, j).or_else(|| tokens.get(j).copied().map(basename_range))
+        }
+        ("accelerate", Some("launch")) => find_launched_script(tokens, i + 2),
+        ("torchrun" | "deepspeed", _) => find_launched_script(tokens, i + 1),
+        _ => None,
+    }
+}
+
+// returns the index of the first token from `i` that's not an option
+fn skip_options(tokens: &[(usize, &str)], mut i: usize, value_options: &[&str]) -> usize {
+    while let Some((_, token)) = tokens.get(i) {
+        if !token.starts_with('-') {
+            break;
+        }
+        i += match value_options.contains(token) {
+            true => 2,
+            false => 1,
+        };
+    }
+    i
+}
+
+fn find_python_target(tokens: &[(usize, &str)], i: usize) -> Option<Range<usize>> {
+    let j = skip_options_until_module(tokens, i);
+    let (_, token) = tokens.get(j)?;
+    match *token {
+        "-c" => None,
+        "-m" => {
+            let module = tokens.get(j + 1)?;
+            match LAUNCHER_MODULES.contains(&module.1) {
+                true => find_launched_script(tokens, j + 2).or(Some(token_range(*module))),
+                false => Some(token_range(*module)),
+            }
+        }
+        _ => Some(basename_range(tokens[j])),
+    }
+}
+
+// like `skip_options`, but stops at `-m` and `-c` which end the interpreter options
+fn skip_options_until_module(tokens: &[(usize, &str)], mut i: usize) -> usize {
+    while let Some((_, token)) = tokens.get(i) {
+        if !token.starts_with('-') || *token == "-m" || *token == "-c" {
+            break;
+        }
+        i += match PYTHON_VALUE_OPTIONS.contains(token) {
+            true => 2,
+            false => 1,
+        };
+    }
+    i
+}
+
+// launchers mix their own options (with and without values) before the script,
+// so we look for the first python script, or the module given with `-m`/`--module`
+fn find_launched_script(tokens: &[(usize, &str)], i: usize) -> Option<Range<usize>> {
+    let rest = tokens.get(i..)?;
+    if let Some(script) = rest.iter().find(|(_, t)| t.ends_with(".py")) {
+        return Some(basename_range(*script));
+    }
+    let module = rest
+        .iter()
+        .position(|(_, t)| *t == "-m" || *t == "--module")?;
+    rest.get(module + 1)
+        .filter(|(_, t)| !t.starts_with('-'))
+        .map(|t| token_range(*t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_interpreter_launch, target_range};
+
+    fn target(cmd: &str) -> &str {
+        &cmd[target_range(cmd)]
+    }
+
+    #[test]
+    fn test_target_binary() {
+        assert_eq!(target("/usr/bin/bash"), "bash");
+        assert_eq!(target("/usr/bin/rsync -a /data/ /backup/"), "rsync");
+        assert_eq!(target(""), "");
+    }
+
+    #[test]
+    fn test_target_python() {
+        assert_eq!(
+            target("/opt/conda/bin/python3.11 -u /src/train.py --lr 1"),
+            "train.py"
+        );
+        assert_eq!(
+            target("python -m vllm.entrypoints.api_server"),
+            "vllm.entrypoints.api_server"
+        );
+        assert_eq!(target("python -W ignore train.py"), "train.py");
+        assert_eq!(target("python -c import torch"), "python");
+        assert_eq!(target("python"), "python");
+        assert_eq!(
+            target("python -m torch.distributed.run --nproc_per_node 8 train.py --lr 1e-4"),
+            "train.py"
+        );
+    }
+
+    #[test]
+    fn test_is_interpreter_launch() {
+        assert!(is_interpreter_launch("/usr/bin/python3 train.py"));
+        assert!(is_interpreter_launch("torchrun train.py"));
+        assert!(!is_interpreter_launch("/usr/bin/python-config"));
+        assert!(!is_interpreter_launch("/usr/bin/bash"));
+    }
+
+    #[test]
+    fn test_target_launchers() {
+        assert_eq!(target("conda run -n ml python eval.py"), "eval.py");
+        assert_eq!(target("uv run --with torch scripts/bench.py"), "bench.py");
+        assert_eq!(target("uv run pytest -x"), "pytest");
+        assert_eq!(
+            target("accelerate launch --num_processes 2 finetune.py"),
+            "finetune.py"
+        );
+        assert_eq!(
+            target("/usr/bin/torchrun --nnodes 2 -m mypkg.train"),
+            "mypkg.train"
+        );
+        assert_eq!(
+            target("deepspeed --num_gpus 4 ds_train.py --deepspeed"),
+            "ds_train.py"
+        );
+        assert_eq!(target("torchrun --standalone"), "torchrun");
+    }
+}
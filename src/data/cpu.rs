@@ -1,52 +1,274 @@
-use sysinfo::{Components, System};
+use std::fs::read_to_string;
 
-use std::collections::HashMap;
+use sysinfo::System;
 
 #[derive(Clone)]
 pub struct CoreSnapshot {
     pub usage: f32,
     pub temp: f32,
+    pub frequency: u64, // MHz
+    pub breakdown: CpuBreakdown,
 }
 
 #[derive(Clone)]
 pub struct CpuSnapshot {
     pub usage: f32, // as a value between 0.0 and 100.0
     pub cores: Vec<CoreSnapshot>,
+    pub breakdown: CpuBreakdown,
 }
 
-impl CpuSnapshot {
-    pub fn from_sysinfo(sys: &System, components: &Components) -> CpuSnapshot {
-        let mut cores: Vec<CoreSnapshot> = Vec::new();
+/// Cumulative time a cpu spent in each state, in clock ticks, from a `cpu` line
+/// of `/proc/stat`. The guest time is already part of the user time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
 
-        // TODO: Fix temperature mismatched for all cores. Have to find a more robust way
-        // to find the 1:1 core temperatures.
+impl CpuTimes {
+    fn parse(fields: &[&str]) -> Option<CpuTimes> {
+        let number = |i: usize| -> Option<u64> { fields.get(i)?.parse().ok() };
+        Some(CpuTimes {
+            user: number(0)?,
+            nice: number(1)?,
+            system: number(2)?,
+            idle: number(3)?,
+            iowait: number(4)?,
+            irq: number(5)?,
+            softirq: number(6)?,
+            steal: number(7)?,
+        })
+    }
+
+    fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+}
 
-        // gather temperatures from Components in to a map
-        let mut temperatures = HashMap::new();
-        for component in components {
-            let label = component.label();
-            if label.contains("Core") {
-                if let Ok(id) = label.split_whitespace().last().unwrap().parse::<usize>() {
-                    temperatures.insert(id, component.temperature());
+/// The times of all the cpus together and of each cpu, by cpu id
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CpuTimesSample {
+    pub total: CpuTimes,
+    pub cpus: Vec<CpuTimes>,
+}
+
+impl CpuTimesSample {
+    pub fn read() -> CpuTimesSample {
+        read_to_string("/proc/stat")
+            .map(|c| Self::parse(&c))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(contents: &str) -> CpuTimesSample {
+        let mut sample = CpuTimesSample::default();
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some((name, rest)) = fields.split_first() else {
+                continue;
+            };
+            let Some(times) = CpuTimes::parse(rest) else {
+                continue;
+            };
+            match name.strip_prefix("cpu") {
+                Some("") => sample.total = times,
+                Some(id) => {
+                    let Ok(id) = id.parse::<usize>() else {
+                        continue;
+                    };
+                    if sample.cpus.len() <= id {
+                        sample.cpus.resize(id + 1, CpuTimes::default());
+                    }
+                    sample.cpus[id] = times;
                 }
+                None => {}
             }
         }
+        sample
+    }
+}
+
+/// The share of time a cpu spent in each busy state, as values between 0.0 and 100.0
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuBreakdown {
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+}
+
+impl CpuBreakdown {
+    /// The breakdown between two readings of the same cpu
+    pub fn between(previous: &CpuTimes, current: &CpuTimes) -> CpuBreakdown {
+        let total = current.total().saturating_sub(previous.total());
+        if total == 0 {
+            return CpuBreakdown::default();
+        }
+        let percent = |f: fn(&CpuTimes) -> u64| {
+            f(current).saturating_sub(f(previous)) as f32 * 100.0 / total as f32
+        };
+        CpuBreakdown {
+            user: percent(|t| t.user),
+            nice: percent(|t| t.nice),
+            system: percent(|t| t.system),
+            iowait: percent(|t| t.iowait),
+            irq: percent(|t| t.irq),
+            softirq: percent(|t| t.softirq),
+            steal: percent(|t| t.steal),
+        }
+    }
+
+    /// Whether there's anything to show, which isn't the case before the second reading
+    pub fn is_known(&self) -> bool {
+        *self != CpuBreakdown::default()
+    }
+}
+
+/// Turns the cumulative times of `/proc/stat` into breakdowns between refreshes
+#[derive(Default)]
+pub struct CpuTimesTracker {
+    previous: Option<CpuTimesSample>,
+}
+
+impl CpuTimesTracker {
+    pub fn measure(&mut self) -> (CpuBreakdown, Vec<CpuBreakdown>) {
+        self.update(CpuTimesSample::read())
+    }
+
+    /// The breakdown of all the cpus together and of each cpu since the previous
+    /// sample, all zeroes on the first one
+    pub fn update(&mut self, sample: CpuTimesSample) -> (CpuBreakdown, Vec<CpuBreakdown>) {
+        let previous = self.previous.replace(sample);
+        let (Some(previous), Some(current)) = (previous, self.previous.as_ref()) else {
+            return (CpuBreakdown::default(), Vec::new());
+        };
+        let cpus = current
+            .cpus
+            .iter()
+            .enumerate()
+            .map(|(i, c)| match previous.cpus.get(i) {
+                Some(p) => CpuBreakdown::between(p, c),
+                None => CpuBreakdown::default(),
+            })
+            .collect();
+        (CpuBreakdown::between(&previous.total, &current.total), cpus)
+    }
+}
+
+impl CpuSnapshot {
+    /// `temperatures` and `breakdowns` hold the temperature and time breakdown of
+    /// each logical cpu, by cpu id
+    pub fn from_sysinfo(
+        sys: &System,
+        temperatures: &[f32],
+        breakdown: CpuBreakdown,
+        breakdowns: &[CpuBreakdown],
+    ) -> CpuSnapshot {
+        let mut cores: Vec<CoreSnapshot> = Vec::new();
 
         for cpu in sys.cpus() {
             let id: usize = cpu.name()[3..].parse().unwrap();
             let usage: f32 = cpu.cpu_usage();
-            let temperature: f32 = match temperatures.get(&id).unwrap_or(&None) {
-                Some(t) => *t,
-                None => 0.0,
-            };
             let core = CoreSnapshot {
                 usage,
-                temp: temperature,
+                temp: temperatures.get(id).copied().unwrap_or(0.0),
+                frequency: cpu.frequency(),
+                breakdown: breakdowns.get(id).copied().unwrap_or_default(),
             };
             cores.push(core);
         }
 
         let usage = sys.global_cpu_usage();
-        CpuSnapshot { usage, cores }
+        CpuSnapshot {
+            usage,
+            cores,
+            breakdown,
+        }
+    }
+
+    /// The (min, average, max) frequency of the cores in MHz, if it's known
+    pub fn frequency_range(&self) -> Option<(u64, u64, u64)> {
+        let frequencies: Vec<u64> = self
+            .cores
+            .iter()
+            .map(|c| c.frequency)
+            .filter(|f| *f > 0)
+            .collect();
+        let min = *frequencies.iter().min()?;
+        let max = *frequencies.iter().max()?;
+        let avg = frequencies.iter().sum::<u64>() / frequencies.len() as u64;
+        Some((min, avg, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CoreSnapshot, CpuBreakdown, CpuSnapshot, CpuTimesSample, CpuTimesTracker};
+
+    const STAT: &str = "cpu  1000 100 500 8000 200 20 30 150 0 0
+cpu0 500 50 250 4000 100 10 15 75 0 0
+cpu1 500 50 250 4000 100 10 15 75 0 0
+
+intr 123456 0 0
+ctxt 987654
+";
+
+    #[test]
+    fn test_breakdown() {
+        let first = CpuTimesSample::parse(STAT);
+        assert_eq!(first.cpus.len(), 2);
+        assert_eq!(first.total.steal, 150);
+
+        // cpu1 spends 1000 ticks: 400 user, 100 system, 300 iowait, 200 stolen
+        let mut second = first.clone();
+        second.cpus[1].user += 400;
+        second.cpus[1].system += 100;
+        second.cpus[1].iowait += 300;
+        second.cpus[1].steal += 200;
+
+        let mut tracker = CpuTimesTracker::default();
+        let (_, cpus) = tracker.update(first);
+        assert!(cpus.is_empty());
+        let (_, cpus) = tracker.update(second);
+        assert!(!cpus[0].is_known());
+        assert_eq!(cpus[1].user, 40.0);
+        assert_eq!(cpus[1].system, 10.0);
+        assert_eq!(cpus[1].iowait, 30.0);
+        assert_eq!(cpus[1].steal, 20.0);
+    }
+
+    #[test]
+    fn test_frequency_range() {
+        let core = |frequency| CoreSnapshot {
+            usage: 0.0,
+            temp: 0.0,
+            frequency,
+            breakdown: CpuBreakdown::default(),
+        };
+        let mut snap = CpuSnapshot {
+            usage: 0.0,
+            cores: vec![core(0); 3],
+            breakdown: CpuBreakdown::default(),
+        };
+        assert_eq!(snap.frequency_range(), None);
+
+        snap.cores = vec![core(1200), core(3000), core(0)];
+        // cores without a known frequency are ignored
+        assert_eq!(snap.frequency_range(), Some((1200, 2100, 3000)));
     }
 }
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fs::{read_dir, read_to_string};
+use std::path::Path;
+
+use super::clock::RateClock;
+use crate::constants::SYSFS_ROOT;
+
+// /proc/diskstats counts sectors of 512 bytes, whatever the actual sector size
+const SECTOR_SIZE: u64 = 512;
+
+// devices that aren't backed by a disk
+const VIRTUAL_DEVICES: [&str; 5] = ["loop", "ram", "zram", "sr", "fd"];
+
+/// The counters of a block device, from a line of `/proc/diskstats`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskStat {
+    pub name: String,
+    pub reads: u64,
+    pub sectors_read: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    // milliseconds spent doing I/O
+    pub io_ticks: u64,
+}
+
+impl DiskStat {
+    pub fn read_all() -> Vec<DiskStat> {
+        let stats = read_to_string("/proc/diskstats")
+            .map(|c| Self::parse_all(&c))
+            .unwrap_or_default();
+        Self::without_stacked(stats, Path::new(SYSFS_ROOT))
+    }
+
+    /// Drops the device-mapper and md devices, like LVM volumes or software RAIDs,
+    /// that only sit on the other disks, since their I/O already shows up there
+    pub fn without_stacked(stats: Vec<DiskStat>, sysfs: &Path) -> Vec<DiskStat> {
+        let disks: Vec<&str> = stats.iter().map(|d| d.name.as_str()).collect();
+        let stacked: Vec<bool> = stats
+            .iter()
+            .map(|d| is_stacked_on(sysfs, &d.name, &disks))
+            .collect();
+        stats
+            .into_iter()
+            .zip(stacked)
+            .filter(|(_, stacked)| !stacked)
+            .map(|(d, _)| d)
+            .collect()
+    }
+
+    /// Parses the whole disks of `/proc/diskstats`, skipping partitions and virtual devices
+    pub fn parse_all(contents: &str) -> Vec<DiskStat> {
+        contents
+            .lines()
+            .filter_map(Self::parse)
+            .filter(|d| is_whole_disk(&d.name))
+            .collect()
+    }
+
+    fn parse(line: &str) -> Option<DiskStat> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| -> Option<u64> { fields.get(i)?.parse().ok() };
+        Some(DiskStat {
+            name: fields.get(2)?.to_string(),
+            reads: number(3)?,
+            sectors_read: number(5)?,
+            writes: number(7)?,
+            sectors_written: number(9)?,
+            io_ticks: number(12)?,
+        })
+    }
+}
+
+fn is_whole_disk(name: &str) -> bool {
+    if VIRTUAL_DEVICES.iter().any(|v| name.starts_with(v)) {
+        return false;
+    }
+    // partitions are e.g. nvme0n1p2 and mmcblk0p1, or sda1 and vdb3
+    match name.starts_with("nvme") || name.starts_with("mmcblk") {
+        true => !name.rsplit_once('p').is_some_and(|(disk, part)| {
+            disk.ends_with(|c: char| c.is_ascii_digit())
+                && !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_digit())
+        }),
+        false => {
+            !(["sd", "vd", "xvd", "hd"]
+                .iter()
+                .any(|p| name.starts_with(p))
+                && name.ends_with(|c: char| c.is_ascii_digit()))
+        }
+    }
+}
+
+// whether all the devices under `name`, from `/sys/block/<name>/slaves`, are
+// `disks` or their partitions, going down through stacked devices like LVM on RAID
+fn is_stacked_on(sysfs: &Path, name: &str, disks: &[&str]) -> bool {
+    let Ok(entries) = read_dir(sysfs.join("block").join(name).join("slaves")) else {
+        return false;
+    };
+    let slaves: Vec<String> = entries
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .collect();
+    let on_disk = |slave: &str| {
+        disks.iter().any(|disk| {
+            *disk != name && (*disk == slave || sysfs.join("block").join(disk).join(slave).is_dir())
+        })
+    };
+    !slaves.is_empty()
+        && slaves
+            .iter()
+            .all(|s| on_disk(s) || is_stacked_on(sysfs, s, disks))
+}
+
+/// Throughput of a disk between two snapshots
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskSnapshot {
+    pub name: String,
+    // bytes per second
+    pub read_rate: u64,
+    // bytes per second
+    pub write_rate: u64,
+    pub read_iops: f32,
+    pub write_iops: f32,
+    // percentage 0-100% of the time the disk was busy
+    pub utilization: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisksSnapshot {
+    pub disks: Vec<DiskSnapshot>,
+}
+
+impl DisksSnapshot {
+    /// The utilization of the busiest disk
+    pub fn max_utilization(&self) -> Option<f32> {
+        self.disks.iter().map(|d| d.utilization).reduce(f32::max)
+    }
+}
+
+/// Turns the counters of `/proc/diskstats` into rates between refreshes
+#[derive(Default)]
+pub struct DiskTracker {
+    previous: HashMap<String, DiskStat>,
+    clock: RateClock,
+}
+
+impl DiskTracker {
+    pub fn measure(&mut self) -> DisksSnapshot {
+        let elapsed = self.clock.elapsed();
+        self.update(DiskStat::read_all(), elapsed)
+    }
+
+    /// Like `measure`, with the stats already read and the `elapsed` of its clock
+    pub fn update(&mut self, stats: Vec<DiskStat>, elapsed: Option<f64>) -> DisksSnapshot {
+        let elapsed = elapsed.filter(|e| *e > 0.0);
+        let disks = stats
+            .iter()
+            .map(|stat| {
+                let previous = self.previous.get(&stat.name);
+                let rate = |f: fn(&DiskStat) -> u64| -> f64 {
+                    match (previous, elapsed) {
+                        (Some(p), Some(e)) => f(stat).saturating_sub(f(p)) as f64 / e,
+                        _ => 0.0,
+                    }
+                };
+                DiskSnapshot {
+                    name: stat.name.clone(),
+                    read_rate: (rate(|s| s.sectors_read) * SECTOR_SIZE as f64) as u64,
+                    write_rate: (rate(|s| s.sectors_written) * SECTOR_SIZE as f64) as u64,
+                    read_iops: rate(|s| s.reads) as f32,
+                    write_iops: rate(|s| s.writes) as f32,
+                    // io_ticks is in ms, so ms/s / 10 gives the percentage
+                    utilization: (rate(|s| s.io_ticks) / 10.0).min(100.0) as f32,
+                }
+            })
+            .collect();
+        self.previous = stats.into_iter().map(|s| (s.name.clone(), s)).collect();
+        DisksSnapshot { disks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiskStat, DiskTracker};
+    use std::fs::read_to_string;
+    use std::path::{Path, PathBuf};
+
+    fn read_fixture(name: &str) -> Vec<DiskStat> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/diskstats")
+            .join(name);
+        DiskStat::parse_all(&read_to_string(&path).expect("missing fixture"))
+    }
+
+    #[test]
+    fn test_whole_disks() {
+        let names: Vec<String> = read_fixture("before.txt")
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(names, ["nvme0n1", "sda", "dm-0"]);
+    }
+
+    #[test]
+    fn test_without_stacked() {
+        let sysfs = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs/disks");
+        assert!(sysfs.is_dir(), "missing fixture {:?}", sysfs);
+        let names =
+            |stats: Vec<DiskStat>| -> Vec<String> { stats.into_iter().map(|d| d.name).collect() };
+
+        // dm-0 is an LVM volume on nvme0n1p2
+        let stats = DiskStat::without_stacked(read_fixture("before.txt"), &sysfs);
+        assert_eq!(names(stats), ["nvme0n1", "sda"]);
+
+        // without nvme0n1 in the list, its I/O isn't counted anywhere else
+        let stats: Vec<DiskStat> = read_fixture("before.txt")
+            .into_iter()
+            .filter(|d| d.name != "nvme0n1")
+            .collect();
+        assert_eq!(
+            names(DiskStat::without_stacked(stats, &sysfs)),
+            ["sda", "dm-0"]
+        );
+
+        // nothing is stacked without sysfs
+        let stats =
+            DiskStat::without_stacked(read_fixture("before.txt"), Path::new("/nonexistent"));
+        assert_eq!(names(stats), ["nvme0n1", "sda", "dm-0"]);
+    }
+
+    #[test]
+    fn test_rates() {
+        let mut tracker = DiskTracker::default();
+        let first = tracker.update(read_fixture("before.txt"), None);
+        assert_eq!(first.disks[0].read_rate, 0);
+
+        // 2 seconds later
+        let second = tracker.update(read_fixture("after.txt"), Some(2.0));
+        let nvme = &second.disks[0];
+        assert_eq!(nvme.name, "nvme0n1");
+        assert_eq!(nvme.read_rate, 200 * 1024 * 1024);
+        assert_eq!(nvme.write_rate, 10 * 1024 * 1024);
+        assert_eq!(nvme.read_iops, 1600.0);
+        assert_eq!(nvme.write_iops, 80.0);
+        assert_eq!(nvme.utilization, 75.0);
+        assert_eq!(second.max_utilization(), Some(75.0));
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// how often the network filesystems are read, on their own thread
+const NETWORK_INTERVAL: Duration = Duration::from_secs(5);
+// how often the filesystems are read for the warnings while their panel is hidden
+const HIDDEN_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// filesystems without a block device that still hold data
+const NETWORK_FILESYSTEMS: [&str; 7] = ["nfs", "nfs4", "cifs", "lustre", "gpfs", "beegfs", "ceph"];
+
+/// A mounted filesystem, from `/proc/mounts`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mount {
+    pub device: String,
+    pub path: String,
+    pub fs_type: String,
+}
+
+impl Mount {
+    pub fn read_all() -> Vec<Mount> {
+        read_to_string("/proc/mounts")
+            .map(|c| Self::parse_mounts(&c))
+            .unwrap_or_default()
+    }
+
+    /// Parses the filesystems of `/proc/mounts` backed by a disk or the network,
+    /// skipping pseudo filesystems like proc, loop devices and repeated mount points
+    pub fn parse_mounts(contents: &str) -> Vec<Mount> {
+        let mut seen = HashSet::new();
+        contents
+            .lines()
+            .filter_map(|l| {
+                let fields: Vec<&str> = l.split_whitespace().collect();
+                // spaces in paths are escaped as \040
+                let unescape = |s: &str| s.replace("\\040", " ");
+                Some(Mount {
+                    device: unescape(fields.first()?),
+                    path: unescape(fields.get(1)?),
+                    fs_type: fields.get(2)?.to_string(),
+                })
+            })
+            .filter(|m| {
+                (m.device.starts_with("/dev/") && !m.device.starts_with("/dev/loop"))
+                    || NETWORK_FILESYSTEMS.contains(&m.fs_type.as_str())
+            })
+            .filter(|m| seen.insert(m.path.clone()))
+            .collect()
+    }
+}
+
+/// (used, total) bytes of the filesystem mounted at `path`
+pub fn filesystem_usage(path: &Path) -> Option<(u64, u64)> {
+    let path = CString::new(path.to_str()?).ok()?;
+    // SAFETY: statvfs is plain data, so all zeroes is a valid value
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid C string and `stat` a valid statvfs to write to
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block_size = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block_size;
+    let free = stat.f_bfree as u64 * block_size;
+    Some((total.saturating_sub(free), total))
+}
+
+/// Used and total space of a filesystem. All values in bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilesystemSnapshot {
+    pub path: String,
+    pub device: String,
+    pub used: u64,
+    pub total: u64,
+}
+
+impl FilesystemSnapshot {
+    pub fn percent(&self) -> f32 {
+        match self.total {
+            0 => 0.0,
+            total => self.used as f32 / total as f32 * 100.0,
+        }
+    }
+
+    pub fn free(&self) -> u64 {
+        self.total.saturating_sub(self.used)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilesystemsSnapshot {
+    pub filesystems: Vec<FilesystemSnapshot>,
+}
+
+impl FilesystemsSnapshot {
+    /// The (path, device) to show. A path that isn't a mount point itself gets
+    /// the device of the mount it's on.
+    fn select(mounts: &[Mount], paths: &[String]) -> Vec<(String, String)> {
+        if paths.is_empty() {
+            return mounts
+                .iter()
+                .map(|m| (m.path.clone(), m.device.clone()))
+                .collect();
+        }
+        paths
+            .iter()
+            .map(|path| {
+                let device = mounts
+                    .iter()
+                    .filter(|m| Path::new(path).starts_with(&m.path))
+                    .max_by_key(|m| m.path.len())
+                    .map_or_else(String::new, |m| m.device.clone());
+                (path.clone(), device)
+            })
+            .collect()
+    }
+}
+
+// what the refreshes and the thread reading the network filesystems share
+#[derive(Default)]
+struct NetworkMounts {
+    // the paths the thread reads
+    paths: Vec<String>,
+    // the last (used, total) bytes it read, by path
+    usage: HashMap<String, (u64, u64)>,
+}
+
+/// Reads the usage of the filesystems without letting a hung network mount freeze
+/// mltop: network mounts are read by a single background thread every few seconds,
+/// and the refreshes show what it last read. A mount is left out until it answers.
+#[derive(Default)]
+pub struct FilesystemSampler {
+    // started with the first network mount
+    network: Option<Arc<Mutex<NetworkMounts>>>,
+}
+
+impl FilesystemSampler {
+    /// Reads the usage of `paths`, or of every mounted filesystem if empty
+    pub fn read(&mut self, paths: &[String]) -> FilesystemsSnapshot {
+        let mounts = Mount::read_all();
+        let filesystems = FilesystemsSnapshot::select(&mounts, paths);
+        // only the network filesystems have no block device
+        let network_paths: Vec<String> = filesystems
+            .iter()
+            .filter(|(_, device)| !device.starts_with("/dev/"))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let network_usage = self.network_usage(network_paths);
+        let filesystems = filesystems
+            .into_iter()
+            .filter_map(|(path, device)| {
+                let (used, total) = match device.starts_with("/dev/") {
+                    true => filesystem_usage(Path::new(&path))?,
+                    false => *network_usage.get(&path)?,
+                };
+                Some(FilesystemSnapshot {
+                    path,
+                    device,
+                    used,
+                    total,
+                })
+            })
+            .collect();
+        FilesystemsSnapshot { filesystems }
+    }
+
+    // hands `paths` to the background thread, and returns what it last read
+    fn network_usage(&mut self, paths: Vec<String>) -> HashMap<String, (u64, u64)> {
+        if paths.is_empty() && self.network.is_none() {
+            return HashMap::new();
+        }
+        let network = self.network.get_or_insert_with(|| {
+            let network = Arc::new(Mutex::new(NetworkMounts {
+                paths: paths.clone(),
+                usage: HashMap::new(),
+            }));
+            let shared = Arc::clone(&network);
+            thread::spawn(move || read_network_mounts(&shared));
+            network
+        });
+        let mut network = network.lock().unwrap();
+        network.paths = paths;
+        network.usage.clone()
+    }
+}
+
+// reads the usage of the network mounts forever, without holding the lock while a
+// mount hangs, so the refreshes never wait for it
+fn read_network_mounts(network: &Mutex<NetworkMounts>) {
+    loop {
+        let paths = network.lock().unwrap().paths.clone();
+        for path in paths {
+            let usage = filesystem_usage(Path::new(&path));
+            let mut network = network.lock().unwrap();
+            match usage {
+                Some(usage) => network.usage.insert(path, usage),
+                None => network.usage.remove(&path),
+            };
+        }
+        let mut network = network.lock().unwrap();
+        let paths = network.paths.clone();
+        network.usage.retain(|path, _| paths.contains(path));
+        drop(network);
+        thread::sleep(NETWORK_INTERVAL);
+    }
+}
+
+/// Remembers which filesystems are over the warning threshold, so we only
+/// warn once when a filesystem crosses it
+#[derive(Default)]
+pub struct LowSpaceWatch {
+    full: HashSet<String>,
+    checked_at: Option<Instant>,
+}
+
+impl LowSpaceWatch {
+    /// Whether the filesystems should be read again for the warnings alone, when
+    /// their panel is hidden: a full disk doesn't need to be caught within a second
+    pub fn due(&self, now: Instant) -> bool {
+        self.checked_at
+            .is_none_or(|checked_at| now.duration_since(checked_at) >= HIDDEN_CHECK_INTERVAL)
+    }
+
+    /// The filesystems that went over `threshold` percent since the previous check
+    pub fn check<'a>(
+        &mut self,
+        snapshot: &'a FilesystemsSnapshot,
+        threshold: f32,
+        now: Instant,
+    ) -> Vec<&'a FilesystemSnapshot> {
+        self.checked_at = Some(now);
+        let (full, below): (Vec<_>, Vec<_>) = snapshot
+            .filesystems
+            .iter()
+            .partition(|f| f.percent() >= threshold);
+        for f in below {
+            self.full.remove(&f.path);
+        }
+        full.into_iter()
+            .filter(|f| self.full.insert(f.path.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilesystemSampler, FilesystemSnapshot, FilesystemsSnapshot, LowSpaceWatch, Mount};
+    use std::time::{Duration, Instant};
+
+    const MOUNTS: &str = "sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+/dev/loop3 /snap/core/123 squashfs ro,nodev,relatime 0 0
+/dev/nvme1n1 /data xfs rw,relatime 0 0
+/dev/nvme1n1 /data xfs rw,relatime 0 0
+tmpfs /dev/shm tmpfs rw,nosuid,nodev 0 0
+nas:/export/scratch /mnt/my\\040scratch nfs4 rw,relatime 0 0
+";
+
+    #[test]
+    fn test_parse_mounts() {
+        let mounts = Mount::parse_mounts(MOUNTS);
+        let paths: Vec<&str> = mounts.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["/", "/data", "/mnt/my scratch"]);
+    }
+
+    #[test]
+    fn test_select_paths() {
+        let mounts = Mount::parse_mounts(MOUNTS);
+        let selected =
+            FilesystemsSnapshot::select(&mounts, &["/data/checkpoints".into(), "/home".into()]);
+        assert_eq!(
+            selected,
+            [
+                ("/data/checkpoints".to_string(), "/dev/nvme1n1".to_string()),
+                ("/home".to_string(), "/dev/nvme0n1p2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_network_usage() {
+        let mut sampler = FilesystemSampler::default();
+        assert!(sampler.network_usage(Vec::new()).is_empty());
+        assert!(sampler.network.is_none());
+
+        // the first refresh doesn't wait for the thread
+        assert!(sampler.network_usage(vec!["/".to_string()]).is_empty());
+        for _ in 0..100 {
+            if sampler
+                .network_usage(vec!["/".to_string()])
+                .contains_key("/")
+            {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the usage of / was never read");
+    }
+
+    #[test]
+    fn test_warns_once() {
+        let snapshot = |used| FilesystemsSnapshot {
+            filesystems: vec![FilesystemSnapshot {
+                path: "/data".to_string(),
+                device: "/dev/nvme1n1".to_string(),
+                used,
+                total: 100,
+            }],
+        };
+        let now = Instant::now();
+        let mut watch = LowSpaceWatch::default();
+        assert!(watch.due(now));
+        assert!(watch.check(&snapshot(50), 90.0, now).is_empty());
+        assert!(!watch.due(now + Duration::from_secs(29)));
+        assert!(watch.due(now + Duration::from_secs(30)));
+        assert_eq!(watch.check(&snapshot(95), 90.0, now).len(), 1);
+        assert!(watch.check(&snapshot(96), 90.0, now).is_empty());
+        // it warns again once it went back below the threshold
+        assert!(watch.check(&snapshot(80), 90.0, now).is_empty());
+        assert_eq!(watch.check(&snapshot(92), 90.0, now).len(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::fs::read_to_string;
+
+use crate::constants::BYTES_PER_MB;
+
+use super::command::is_interpreter_launch;
+use super::processes::Process;
+
+/// ML framework a process is running, inferred from its loaded shared libraries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framework {
+    Vllm,
+    Torch,
+    TensorFlow,
+    Jax,
+}
+
+// an interpreter without a framework has its maps read again after this many
+// refreshes, or as soon as its memory grew by this much, as importing a
+// framework maps hundreds of MB of libraries
+const RECHECK_REFRESHES: u32 = 10;
+const RECHECK_MEMORY_GROWTH: u64 = 64 * BYTES_PER_MB;
+
+// in order of priority, since e.g. vLLM also loads torch
+const SIGNATURES: [(Framework, &[&str]); 4] = [
+    (Framework::Vllm, &["/vllm/"]),
+    (Framework::Torch, &["/libtorch"]),
+    (Framework::TensorFlow, &["/libtensorflow"]),
+    (Framework::Jax, &["/jaxlib/", "/jax_plugins/"]),
+];
+
+impl Display for Framework {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Framework::Vllm => write!(f, "vllm"),
+            Framework::Torch => write!(f, "torch"),
+            Framework::TensorFlow => write!(f, "tensorflow"),
+            Framework::Jax => write!(f, "jax"),
+        }
+    }
+}
+
+impl Framework {
+    /// Detects the framework from the contents of `/proc/<pid>/maps`
+    pub fn from_maps(maps: &str) -> Option<Framework> {
+        let libraries: Vec<&str> = maps
+            .lines()
+            .filter_map(|l| l.split_whitespace().nth(5))
+            .filter(|path| path.contains(".so"))
+            .collect();
+
+        SIGNATURES
+            .iter()
+            .find(|(_, patterns)| {
+                libraries
+                    .iter()
+                    .any(|lib| patterns.iter().any(|p| lib.contains(p)))
+            })
+            .map(|(framework, _)| *framework)
+    }
+
+    pub fn read(pid: u32) -> Option<Framework> {
+        Self::from_maps(&read_to_string(format!("/proc/{}/maps", pid)).ok()?)
+    }
+}
+
+// what the memory maps of a process showed when they were last read
+struct Detection {
+    framework: Option<Framework>,
+    // resident memory in bytes at the time
+    memory: u64,
+    // refreshes since
+    age: u32,
+}
+
+impl Detection {
+    fn is_stale(&self, memory: u64) -> bool {
+        self.age >= RECHECK_REFRESHES || memory >= self.memory + RECHECK_MEMORY_GROWTH
+    }
+}
+
+/// Remembers the detected framework of each process, to avoid reading
+/// the (potentially large) memory maps of every process on every refresh.
+#[derive(Default)]
+pub struct FrameworkCache {
+    // keyed by (pid, start time) to not get confused by reused pids
+    frameworks: HashMap<(u32, u64), Detection>,
+}
+
+impl FrameworkCache {
+    /// Sets the framework of every process and forgets the processes that exited.
+    ///
+    /// Processes without a framework are only checked again if they're interpreter
+    /// launches, since those can import a framework long after they started, and
+    /// then only every few refreshes or when their memory grew.
+    pub fn annotate(&mut self, processes: &mut [Process]) {
+        let mut alive = HashSet::new();
+        for p in processes.iter_mut().filter(|p| !p.is_thread()) {
+            let key = (p.pid, p.start_time);
+            alive.insert(key);
+            p.framework = match self.frameworks.get_mut(&key) {
+                Some(d) if d.framework.is_some() || !is_interpreter_launch(&p.command) => {
+                    d.framework
+                }
+                Some(d) if !d.is_stale(p.memory) => {
+                    d.age += 1;
+                    None
+                }
+                _ => {
+                    let framework = Framework::read(p.pid);
+                    let detection = Detection {
+                        framework,
+                        memory: p.memory,
+                        age: 0,
+                    };
+                    self.frameworks.insert(key, detection);
+                    framework
+                }
+            };
+        }
+        self.frameworks.retain(|key, _| alive.contains(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Detection, Framework, RECHECK_MEMORY_GROWTH, RECHECK_REFRESHES};
+
+    fn maps(libraries: &[&str]) -> String {
+        libraries
+            .iter()
+            .map(|l| {
+                format!(
+                    "7f3c4a000000-7f3c4a200000 r-xp 00000000 103:02 1234   {}\n",
+                    l
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_maps() {
+        let torch = maps(&[
+            "/usr/lib/x86_64-linux-gnu/libc.so.6",
+            "/venv/lib/python3.11/site-packages/torch/lib/libtorch_cpu.so",
+        ]);
+        assert_eq!(Framework::from_maps(&torch), Some(Framework::Torch));
+
+        let vllm = maps(&[
+            "/venv/lib/python3.11/site-packages/torch/lib/libtorch_cuda.so",
+            "/venv/lib/python3.11/site-packages/vllm/_C.abi3.so",
+        ]);
+        assert_eq!(Framework::from_maps(&vllm), Some(Framework::Vllm));
+
+        let tf = maps(&["/venv/site-packages/tensorflow/libtensorflow_framework.so.2"]);
+        assert_eq!(Framework::from_maps(&tf), Some(Framework::TensorFlow));
+
+        let jax = maps(&["/venv/site-packages/jaxlib/xla_extension.so"]);
+        assert_eq!(Framework::from_maps(&jax), Some(Framework::Jax));
+
+        let none = maps(&["/usr/lib/x86_64-linux-gnu/libc.so.6", "[heap]"]);
+        assert_eq!(Framework::from_maps(&none), None);
+    }
+
+    #[test]
+    fn test_detection_is_stale() {
+        let mut detection = Detection {
+            framework: None,
+            memory: 1_000_000_000,
+            age: 0,
+        };
+        assert!(!detection.is_stale(1_000_000_000));
+        // small changes of the resident memory don't count
+        assert!(!detection.is_stale(1_000_000_000 + RECHECK_MEMORY_GROWTH - 1));
+        assert!(!detection.is_stale(900_000_000));
+        assert!(detection.is_stale(1_000_000_000 + RECHECK_MEMORY_GROWTH));
+
+        detection.age = RECHECK_REFRESHES;
+        assert!(detection.is_stale(1_000_000_000));
+    }
+}
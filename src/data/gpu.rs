@@ -1,6 +1,11 @@
 use nvml_wrapper::{error::NvmlError, Nvml};
 
-use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+use nvml_wrapper::enum_wrappers::device::{Clock, PcieUtilCounter, TemperatureSensor};
+use nvml_wrapper::Device;
+use std::path::Path;
+
+use super::numa::pci_numa_node;
+use crate::constants::{BYTES_PER_KB, SYSFS_ROOT};
 
 #[derive(Clone)]
 pub struct GpuSnapshot {
@@ -12,10 +17,64 @@ pub struct GpuSnapshot {
     pub max_power: u32,
     pub power_usage: u32,
     pub fan_speed: Option<u32>,
+    // NUMA node of the PCI slot, on machines with more than one
+    pub numa_node: Option<usize>,
+    pub details: GpuDetails,
+}
+
+/// Extra metrics shown when the GPU panel is expanded. Not every GPU
+/// supports all of them, so they're all optional.
+#[derive(Clone, Default)]
+pub struct GpuDetails {
+    pub graphics_clock: Option<u32>,     // MHz
+    pub max_graphics_clock: Option<u32>, // MHz
+    pub memory_clock: Option<u32>,       // MHz
+    pub pcie_gen: Option<u32>,
+    pub pcie_width: Option<u32>,
+    pub pcie_tx: Option<u64>, // bytes/s
+    pub pcie_rx: Option<u64>, // bytes/s
+    pub encoder_utilization: Option<u32>,
+    pub decoder_utilization: Option<u32>,
+}
+
+impl GpuDetails {
+    fn from_device(device: &Device) -> GpuDetails {
+        let pcie_rate = |counter| {
+            // NVML reports the throughput in KB/s
+            device
+                .pcie_throughput(counter)
+                .ok()
+                .map(|kb| kb as u64 * BYTES_PER_KB)
+        };
+        GpuDetails {
+            graphics_clock: device.clock_info(Clock::Graphics).ok(),
+            max_graphics_clock: device.max_clock_info(Clock::Graphics).ok(),
+            memory_clock: device.clock_info(Clock::Memory).ok(),
+            pcie_gen: device.current_pcie_link_gen().ok(),
+            pcie_width: device.current_pcie_link_width().ok(),
+            pcie_tx: pcie_rate(PcieUtilCounter::Send),
+            pcie_rx: pcie_rate(PcieUtilCounter::Receive),
+            encoder_utilization: device.encoder_utilization().ok().map(|u| u.utilization),
+            decoder_utilization: device.decoder_utilization().ok().map(|u| u.utilization),
+        }
+    }
+}
+
+/// The NUMA node of every GPU, by NVML index. They don't move, so this is only
+/// read once.
+pub fn gpu_numa_nodes(nvml: &Nvml) -> Vec<Option<usize>> {
+    let count = nvml.device_count().unwrap_or(0);
+    (0..count)
+        .map(|i| {
+            let bus_id = nvml.device_by_index(i).ok()?.pci_info().ok()?.bus_id;
+            pci_numa_node(Path::new(SYSFS_ROOT), &bus_id)
+        })
+        .collect()
 }
 
 impl GpuSnapshot {
-    pub fn from_nvml(nvml: &Nvml) -> Result<GpuSnapshot, NvmlError> {
+    /// The `details` are only queried if `with_details`, since they take a dozen NVML calls
+    pub fn from_nvml(nvml: &Nvml, with_details: bool) -> Result<GpuSnapshot, NvmlError> {
         let device = nvml.device_by_index(0)?;
         let memory_info = device.memory_info()?;
 
@@ -31,6 +90,14 @@ impl GpuSnapshot {
                 .unwrap_or(0),
             power_usage: device.power_usage()?,
             fan_speed: device.fan_speed(0).ok(),
+            numa_node: device
+                .pci_info()
+                .ok()
+                .and_then(|p| pci_numa_node(Path::new(SYSFS_ROOT), &p.bus_id)),
+            details: match with_details {
+                true => GpuDetails::from_device(&device),
+                false => GpuDetails::default(),
+            },
         })
     }
 }
@@ -0,0 +1,86 @@
+use std::fs::read_to_string;
+
+use sysinfo::System;
+
+/// Pressure stall information from `/proc/pressure/*`: the percentage of the
+/// last 10 seconds in which some tasks were stalled waiting for the resource.
+/// `None` on kernels without PSI.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pressure {
+    pub cpu: Option<f32>,
+    pub memory: Option<f32>,
+    pub io: Option<f32>,
+}
+
+impl Pressure {
+    pub fn read() -> Pressure {
+        let read = |resource: &str| {
+            read_to_string(format!("/proc/pressure/{}", resource))
+                .ok()
+                .and_then(|c| Self::parse(&c))
+        };
+        Pressure {
+            cpu: read("cpu"),
+            memory: read("memory"),
+            io: read("io"),
+        }
+    }
+
+    /// Parses the avg10 of the "some" line, e.g.
+    /// `some avg10=4.10 avg60=3.88 avg300=2.70 total=63692987`
+    pub fn parse(contents: &str) -> Option<f32> {
+        contents
+            .lines()
+            .find_map(|l| l.strip_prefix("some "))?
+            .split_whitespace()
+            .find_map(|f| f.strip_prefix("avg10="))?
+            .parse()
+            .ok()
+    }
+}
+
+/// What the machine is and how loaded it is, for the header
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostSnapshot {
+    pub hostname: String,
+    pub kernel: String,
+    // seconds since boot
+    pub uptime: u64,
+    // 1, 5 and 15 minute load averages
+    pub load_average: [f64; 3],
+    pub pressure: Pressure,
+}
+
+impl HostSnapshot {
+    pub fn read() -> HostSnapshot {
+        let mut host = HostSnapshot {
+            hostname: System::host_name().unwrap_or_default(),
+            kernel: System::kernel_version().unwrap_or_default(),
+            ..Default::default()
+        };
+        host.refresh();
+        host
+    }
+
+    /// Reads what changes between refreshes, keeping the hostname and kernel
+    pub fn refresh(&mut self) {
+        let load = System::load_average();
+        self.uptime = System::uptime();
+        self.load_average = [load.one, load.five, load.fifteen];
+        self.pressure = Pressure::read();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pressure;
+
+    #[test]
+    fn test_parse_pressure() {
+        let memory = "some avg10=4.10 avg60=3.88 avg300=2.70 total=63692987
+full avg10=1.00 avg60=0.50 avg300=0.20 total=1234
+";
+        assert_eq!(Pressure::parse(memory), Some(4.1));
+        assert_eq!(Pressure::parse(""), None);
+    }
+}
@@ -1,5 +1,12 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
 use sysinfo::System;
 
+use super::filesystem::filesystem_usage;
+use crate::constants::BYTES_PER_KB;
+
 /// Memory usage
 ///
 /// All values in bytes.
@@ -9,6 +16,64 @@ pub struct MemorySnapshot {
     pub total: u64,
     pub used_swap: u64,
     pub total_swap: u64,
+    pub details: MemoryDetails,
+}
+
+/// The breakdown of the memory shown in the expanded memory view, from
+/// `/proc/meminfo` and `/dev/shm`. All values in bytes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryDetails {
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    // page cache and reclaimable kernel memory, without the shared memory
+    pub cache: u64,
+    // tmpfs (including /dev/shm) and shared anonymous memory
+    pub shared: u64,
+    pub huge_pages_total: u64,
+    pub huge_pages_free: u64,
+    pub dev_shm_used: u64,
+    pub dev_shm_total: u64,
+}
+
+impl MemoryDetails {
+    pub fn read() -> MemoryDetails {
+        let mut details = read_to_string("/proc/meminfo")
+            .map(|m| Self::parse_meminfo(&m))
+            .unwrap_or_default();
+        if let Some((used, total)) = filesystem_usage(Path::new("/dev/shm")) {
+            details.dev_shm_used = used;
+            details.dev_shm_total = total;
+        }
+        details
+    }
+
+    pub fn parse_meminfo(contents: &str) -> MemoryDetails {
+        // values are in kB, except for the HugePages_ counts
+        let fields: HashMap<&str, u64> = contents
+            .lines()
+            .filter_map(|l| {
+                let (key, value) = l.split_once(':')?;
+                Some((key, value.split_whitespace().next()?.parse().ok()?))
+            })
+            .collect();
+        let kb = |key: &str| fields.get(key).copied().unwrap_or(0) * BYTES_PER_KB;
+        let huge_page_size = kb("Hugepagesize");
+
+        // same as htop: the shared memory is part of "Cached" but shown separately
+        let shared = kb("Shmem");
+        MemoryDetails {
+            free: kb("MemFree"),
+            available: kb("MemAvailable"),
+            buffers: kb("Buffers"),
+            cache: (kb("Cached") + kb("SReclaimable")).saturating_sub(shared),
+            shared,
+            huge_pages_total: fields.get("HugePages_Total").copied().unwrap_or(0) * huge_page_size,
+            huge_pages_free: fields.get("HugePages_Free").copied().unwrap_or(0) * huge_page_size,
+            dev_shm_used: 0,
+            dev_shm_total: 0,
+        }
+    }
 }
 
 impl MemorySnapshot {
@@ -18,10 +83,50 @@ impl MemorySnapshot {
             total: sys.total_memory(),
             used_swap: sys.used_swap(),
             total_swap: sys.total_swap(),
+            details: MemoryDetails::read(),
         }
     }
 
     pub fn total_percent(&self) -> f64 {
         ((self.used + self.used_swap) as f64) / ((self.total + self.total_swap) as f64)
     }
+
+    /// The memory used by processes, without buffers and caches, like htop shows it
+    pub fn used_by_processes(&self) -> u64 {
+        let d = &self.details;
+        self.total
+            .saturating_sub(d.free)
+            .saturating_sub(d.buffers)
+            .saturating_sub(d.cache)
+            .saturating_sub(d.shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryDetails;
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = "MemTotal:       65536000 kB
+MemFree:        10000000 kB
+MemAvailable:   40000000 kB
+Buffers:          500000 kB
+Cached:         25000000 kB
+SwapCached:            0 kB
+Shmem:           2000000 kB
+SReclaimable:    1000000 kB
+HugePages_Total:      16
+HugePages_Free:        4
+Hugepagesize:       2048 kB
+";
+        let details = MemoryDetails::parse_meminfo(meminfo);
+        assert_eq!(details.free, 10_000_000 * 1024);
+        assert_eq!(details.available, 40_000_000 * 1024);
+        assert_eq!(details.buffers, 500_000 * 1024);
+        assert_eq!(details.shared, 2_000_000 * 1024);
+        assert_eq!(details.cache, 24_000_000 * 1024);
+        assert_eq!(details.huge_pages_total, 16 * 2048 * 1024);
+        assert_eq!(details.huge_pages_free, 4 * 2048 * 1024);
+    }
 }
@@ -1,8 +1,23 @@
+pub mod cgroup;
+pub mod cgroup_limits;
+pub mod clock;
+pub mod command;
 pub mod cpu;
+pub mod disk;
+pub mod filesystem;
+pub mod framework;
 pub mod gpu;
+pub mod host;
 pub mod memory;
+pub mod network;
+pub mod numa;
+pub mod power;
 pub mod processes;
+pub mod procfs;
+pub mod sensors;
 pub mod snapshot;
 pub mod store;
 pub mod system_data;
+pub mod topology;
 pub mod update_kind;
+pub mod users;
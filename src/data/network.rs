@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use super::clock::RateClock;
+
+// interfaces that don't leave the machine
+const VIRTUAL_INTERFACES: [&str; 2] = ["lo", "veth"];
+
+// the InfiniBand data counters count 4-byte words
+const INFINIBAND_WORD_SIZE: u64 = 4;
+
+const BITS_PER_BYTE: u64 = 8;
+
+/// The byte counters of a network interface, from `/proc/net/dev`, or of an
+/// InfiniBand port, from `/sys/class/infiniband`
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceStat {
+    // e.g. `eth0`, or `mlx5_0:1` for port 1 of an InfiniBand device
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    // link speed in bytes per second, if known
+    pub speed: Option<u64>,
+}
+
+impl InterfaceStat {
+    pub fn read_all(sysfs: &Path) -> Vec<InterfaceStat> {
+        let mut stats: Vec<InterfaceStat> = read_to_string("/proc/net/dev")
+            .map(|c| Self::parse_net_dev(&c))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| InterfaceStat {
+                speed: link_speed(sysfs, &s.name),
+                ..s
+            })
+            .collect();
+        stats.extend(Self::read_infiniband(sysfs));
+        stats
+    }
+
+    /// Parses the interfaces of `/proc/net/dev`, skipping the loopback and virtual
+    /// ethernet devices. The link speed is left unknown.
+    pub fn parse_net_dev(contents: &str) -> Vec<InterfaceStat> {
+        // the first two lines are headers
+        contents
+            .lines()
+            .skip(2)
+            .filter_map(|l| {
+                let (name, counters) = l.split_once(':')?;
+                let name = name.trim();
+                if VIRTUAL_INTERFACES.iter().any(|v| name.starts_with(v)) {
+                    return None;
+                }
+                let counters: Vec<&str> = counters.split_whitespace().collect();
+                Some(InterfaceStat {
+                    name: name.to_string(),
+                    rx_bytes: counters.first()?.parse().ok()?,
+                    tx_bytes: counters.get(8)?.parse().ok()?,
+                    speed: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Reads the ports of every InfiniBand device. RDMA traffic doesn't go through
+    /// the kernel's network stack, so it's missing from `/proc/net/dev`.
+    pub fn read_infiniband(sysfs: &Path) -> Vec<InterfaceStat> {
+        let Ok(devices) = read_dir(sysfs.join("class/infiniband")) else {
+            return Vec::new();
+        };
+        let mut stats = Vec::new();
+        for device in devices.flatten() {
+            let Ok(ports) = read_dir(device.path().join("ports")) else {
+                continue;
+            };
+            for port in ports.flatten() {
+                let dir = port.path();
+                let read = |file: &str| -> Option<u64> {
+                    read_to_string(dir.join("counters").join(file))
+                        .ok()?
+                        .trim()
+                        .parse()
+                        .ok()
+                };
+                let (Some(rx), Some(tx)) = (read("port_rcv_data"), read("port_xmit_data")) else {
+                    continue;
+                };
+                stats.push(InterfaceStat {
+                    name: format!(
+                        "{}:{}",
+                        device.file_name().to_string_lossy(),
+                        port.file_name().to_string_lossy()
+                    ),
+                    rx_bytes: rx * INFINIBAND_WORD_SIZE,
+                    tx_bytes: tx * INFINIBAND_WORD_SIZE,
+                    speed: read_to_string(dir.join("rate"))
+                        .ok()
+                        .and_then(|r| parse_infiniband_rate(&r)),
+                });
+            }
+        }
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        stats
+    }
+}
+
+/// The speed of a network interface in bytes per second, from its speed in Mb/s.
+/// Virtual interfaces have no speed, or -1.
+fn link_speed(sysfs: &Path, interface: &str) -> Option<u64> {
+    let path = sysfs.join("class/net").join(interface).join("speed");
+    let megabits: i64 = read_to_string(path).ok()?.trim().parse().ok()?;
+    match megabits > 0 {
+        true => Some(megabits as u64 * 1_000_000 / BITS_PER_BYTE),
+        false => None,
+    }
+}
+
+/// Parses the rate of an InfiniBand port, e.g. `100 Gb/sec (4X EDR)`, into bytes per second
+fn parse_infiniband_rate(rate: &str) -> Option<u64> {
+    let gigabits: f64 = rate.split_whitespace().next()?.parse().ok()?;
+    Some((gigabits * 1e9) as u64 / BITS_PER_BYTE)
+}
+
+/// Throughput of a network interface between two snapshots
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceSnapshot {
+    pub name: String,
+    // bytes per second
+    pub rx_rate: u64,
+    // bytes per second
+    pub tx_rate: u64,
+    // link speed in bytes per second, if known
+    pub speed: Option<u64>,
+}
+
+impl InterfaceSnapshot {
+    /// The busiest direction as a percentage 0-100% of the link speed
+    pub fn utilization(&self) -> Option<f32> {
+        let speed = self.speed?;
+        Some((self.rx_rate.max(self.tx_rate) as f32 / speed as f32 * 100.0).min(100.0))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetworkSnapshot {
+    pub interfaces: Vec<InterfaceSnapshot>,
+}
+
+impl NetworkSnapshot {
+    /// The utilization of the busiest interface with a known link speed
+    pub fn max_utilization(&self) -> Option<f32> {
+        self.interfaces
+            .iter()
+            .filter_map(|i| i.utilization())
+            .reduce(f32::max)
+    }
+}
+
+/// Turns the byte counters of the network interfaces into rates between refreshes
+pub struct NetworkTracker {
+    sysfs: PathBuf,
+    previous: HashMap<String, InterfaceStat>,
+    clock: RateClock,
+}
+
+impl NetworkTracker {
+    pub fn new(sysfs: &Path) -> NetworkTracker {
+        NetworkTracker {
+            sysfs: sysfs.to_path_buf(),
+            previous: HashMap::new(),
+            clock: RateClock::default(),
+        }
+    }
+
+    pub fn measure(&mut self) -> NetworkSnapshot {
+        let elapsed = self.clock.elapsed();
+        let stats = InterfaceStat::read_all(&self.sysfs);
+        self.update(stats, elapsed)
+    }
+
+    /// Like `measure`, with the stats already read and the `elapsed` of its clock
+    pub fn update(&mut self, stats: Vec<InterfaceStat>, elapsed: Option<f64>) -> NetworkSnapshot {
+        let elapsed = elapsed.filter(|e| *e > 0.0);
+        let interfaces = stats
+            .iter()
+            .map(|stat| {
+                let previous = self.previous.get(&stat.name);
+                let rate = |f: fn(&InterfaceStat) -> u64| -> u64 {
+                    match (previous, elapsed) {
+                        (Some(p), Some(e)) => (f(stat).saturating_sub(f(p)) as f64 / e) as u64,
+                        _ => 0,
+                    }
+                };
+                InterfaceSnapshot {
+                    name: stat.name.clone(),
+                    rx_rate: rate(|s| s.rx_bytes),
+                    tx_rate: rate(|s| s.tx_bytes),
+                    speed: stat.speed,
+                }
+            })
+            .collect();
+        self.previous = stats.into_iter().map(|s| (s.name.clone(), s)).collect();
+        NetworkSnapshot { interfaces }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{link_speed, InterfaceStat, NetworkTracker};
+    use std::path::PathBuf;
+
+    const NET_DEV: &str = "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 5000000   40000    0    0    0     0          0         0  5000000   40000    0    0    0     0       0          0
+  eth0: 1000000000 800000    0    0    0     0          0       120 200000000 300000    0    0    0     0       0          0
+vethab12cd: 1000     10    0    0    0     0          0         0     2000      20    0    0    0     0       0          0
+";
+
+    fn fixture() -> PathBuf {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs/network");
+        assert!(path.is_dir(), "missing fixture {:?}", path);
+        path
+    }
+
+    #[test]
+    fn test_parse_net_dev() {
+        let stats = InterfaceStat::parse_net_dev(NET_DEV);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name, "eth0");
+        assert_eq!(stats[0].rx_bytes, 1_000_000_000);
+        assert_eq!(stats[0].tx_bytes, 200_000_000);
+    }
+
+    #[test]
+    fn test_link_speed() {
+        assert_eq!(link_speed(&fixture(), "eth0"), Some(25_000 * 1_000_000 / 8));
+        assert_eq!(link_speed(&fixture(), "docker0"), None);
+    }
+
+    #[test]
+    fn test_infiniband_rates() {
+        let stats = InterfaceStat::read_infiniband(&fixture());
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name, "mlx5_0:1");
+        assert_eq!(stats[0].rx_bytes, 4_000_000);
+        assert_eq!(stats[0].speed, Some(12_500_000_000));
+
+        let mut tracker = NetworkTracker::new(&fixture());
+        tracker.update(stats.clone(), None);
+        // 1.25 GB/s received over 2 seconds, a tenth of the link speed
+        let mut after = stats[0].clone();
+        after.rx_bytes += 2_500_000_000;
+        let snapshot = tracker.update(vec![after], Some(2.0));
+        assert_eq!(snapshot.interfaces[0].rx_rate, 1_250_000_000);
+        assert_eq!(snapshot.interfaces[0].tx_rate, 0);
+        assert_eq!(snapshot.max_utilization(), Some(10.0));
+    }
+}
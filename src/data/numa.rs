@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fs::{read_dir, read_to_string};
+use std::path::Path;
+
+use crate::constants::{BYTES_PER_KB, SYSFS_ROOT};
+use crate::utils::parse_cpu_list;
+
+/// A NUMA node, from `/sys/devices/system/node/node<N>`. Memory in bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+    pub memory_total: u64,
+    // like the used memory of the system, without the page cache
+    pub memory_used: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NumaSnapshot {
+    pub nodes: Vec<NumaNode>,
+    // the node of each GPU, by NVML index
+    pub gpu_nodes: Vec<Option<usize>>,
+}
+
+impl NumaSnapshot {
+    pub fn read() -> NumaSnapshot {
+        Self::read_from(Path::new(SYSFS_ROOT))
+    }
+
+    pub fn read_from(sysfs: &Path) -> NumaSnapshot {
+        let Ok(entries) = read_dir(sysfs.join("devices/system/node")) else {
+            return NumaSnapshot::default();
+        };
+        let mut nodes: Vec<NumaNode> = entries
+            .filter_map(|e| {
+                let e = e.ok()?;
+                let id = e.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+                let dir = e.path();
+                let cpus = parse_cpu_list(&read_to_string(dir.join("cpulist")).ok()?)?;
+                let (memory_total, memory_used) = read_to_string(dir.join("meminfo"))
+                    .map(|m| parse_node_meminfo(&m))
+                    .unwrap_or_default();
+                Some(NumaNode {
+                    id,
+                    cpus,
+                    memory_total,
+                    memory_used,
+                })
+            })
+            .collect();
+        nodes.sort_by_key(|n| n.id);
+        NumaSnapshot {
+            nodes,
+            gpu_nodes: Vec::new(),
+        }
+    }
+
+    /// Whether there's more than one node, so NUMA placement matters
+    pub fn is_numa(&self) -> bool {
+        self.nodes.len() > 1
+    }
+
+    /// The cpus of the nodes of the `gpus`, by NVML index. Empty when their nodes
+    /// aren't known.
+    pub fn gpu_cpus(&self, gpus: &[u32]) -> Vec<usize> {
+        let gpu_nodes: Vec<usize> = gpus
+            .iter()
+            .filter_map(|&gpu| *self.gpu_nodes.get(gpu as usize)?)
+            .collect();
+        self.nodes
+            .iter()
+            .filter(|n| gpu_nodes.contains(&n.id))
+            .flat_map(|n| n.cpus.iter().copied())
+            .collect()
+    }
+
+    /// The node a cpu belongs to
+    pub fn node_of(&self, cpu: usize) -> Option<usize> {
+        self.nodes
+            .iter()
+            .find(|n| n.cpus.contains(&cpu))
+            .map(|n| n.id)
+    }
+}
+
+/// (total, used) bytes from a node's meminfo, whose lines look like
+/// `Node 0 MemTotal:       65536000 kB`
+fn parse_node_meminfo(contents: &str) -> (u64, u64) {
+    let fields: HashMap<&str, u64> = contents
+        .lines()
+        .filter_map(|l| {
+            let (key, value) = l.split_once(':')?;
+            let key = key.split_whitespace().last()?;
+            Some((key, value.split_whitespace().next()?.parse().ok()?))
+        })
+        .collect();
+    let kb = |key: &str| fields.get(key).copied().unwrap_or(0) * BYTES_PER_KB;
+    let total = kb("MemTotal");
+    let used = total
+        .saturating_sub(kb("MemFree"))
+        .saturating_sub(kb("FilePages"))
+        .saturating_sub(kb("SReclaimable"));
+    (total, used)
+}
+
+/// The NUMA node of a PCI device, from `/sys/bus/pci/devices/<bus id>/numa_node`.
+/// `bus_id` can be in NVML's format, e.g. `00000000:3B:00.0`. `None` on machines
+/// without NUMA, where the node is -1.
+pub fn pci_numa_node(sysfs: &Path, bus_id: &str) -> Option<usize> {
+    // sysfs uses a 4 digit domain, in lowercase
+    let (domain, rest) = bus_id.split_once(':')?;
+    let domain = u32::from_str_radix(domain, 16).ok()?;
+    let address = format!("{:04x}:{}", domain, rest.to_lowercase());
+    let path = sysfs
+        .join("bus/pci/devices")
+        .join(address)
+        .join("numa_node");
+    let node: i64 = read_to_string(path).ok()?.trim().parse().ok()?;
+    usize::try_from(node).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pci_numa_node, NumaSnapshot};
+    use std::path::PathBuf;
+
+    fn fixture() -> PathBuf {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs/numa");
+        assert!(path.is_dir(), "missing fixture {:?}", path);
+        path
+    }
+
+    #[test]
+    fn test_read_nodes() {
+        let numa = NumaSnapshot::read_from(&fixture());
+        assert!(numa.is_numa());
+        assert_eq!(numa.nodes[0].cpus, [0, 1, 2, 3, 8, 9, 10, 11]);
+        assert_eq!(numa.nodes[0].memory_total, 65_536_000 * 1024);
+        assert_eq!(numa.nodes[0].memory_used, 30_000_000 * 1024);
+        assert_eq!(numa.nodes[1].memory_used, 4_000_000 * 1024);
+        assert_eq!(numa.node_of(12), Some(1));
+        assert_eq!(numa.node_of(64), None);
+    }
+
+    #[test]
+    fn test_gpu_cpus() {
+        let numa = NumaSnapshot {
+            gpu_nodes: vec![Some(0), Some(1), None],
+            ..NumaSnapshot::read_from(&fixture())
+        };
+        assert_eq!(numa.gpu_cpus(&[0]), [0, 1, 2, 3, 8, 9, 10, 11]);
+        assert_eq!(numa.gpu_cpus(&[1]), numa.nodes[1].cpus);
+        assert_eq!(numa.gpu_cpus(&[0, 1]).len(), 16);
+        assert!(numa.gpu_cpus(&[2]).is_empty());
+        assert!(numa.gpu_cpus(&[7]).is_empty());
+    }
+
+    #[test]
+    fn test_pci_numa_node() {
+        assert_eq!(pci_numa_node(&fixture(), "00000000:3B:00.0"), Some(1));
+        assert_eq!(pci_numa_node(&fixture(), "00000000:AF:00.0"), None);
+    }
+}
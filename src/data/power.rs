@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use super::clock::RateClock;
+
+const JOULES_PER_WH: f64 = 3600.0;
+
+/// An energy counter of RAPL (Running Average Power Limit), from
+/// `/sys/class/powercap/intel-rapl:<N>[:<M>]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaplDomain {
+    // the directory name, e.g. `intel-rapl:0:1`
+    pub id: String,
+    // e.g. `package-0` or `dram`
+    pub name: String,
+    pub energy_uj: u64,
+    // the counter wraps around to 0 after this value
+    pub max_energy_range_uj: u64,
+}
+
+impl RaplDomain {
+    /// Reads every RAPL domain. Usually empty for non-root users, since
+    /// the counters are only readable by root on recent kernels.
+    pub fn read_all(sysfs: &Path) -> Vec<RaplDomain> {
+        let Ok(entries) = read_dir(sysfs.join("class/powercap")) else {
+            return Vec::new();
+        };
+        let mut domains: Vec<RaplDomain> = entries
+            .filter_map(|e| {
+                let e = e.ok()?;
+                let id = e.file_name().to_str()?.to_string();
+                if !id.starts_with("intel-rapl:") {
+                    return None;
+                }
+                let dir = e.path();
+                let read = |file: &str| read_to_string(dir.join(file)).ok();
+                Some(RaplDomain {
+                    name: read("name")?.trim().to_string(),
+                    energy_uj: read("energy_uj")?.trim().parse().ok()?,
+                    max_energy_range_uj: read("max_energy_range_uj")?.trim().parse().ok()?,
+                    id,
+                })
+            })
+            .collect();
+        domains.sort_by(|a, b| a.id.cmp(&b.id));
+        domains
+    }
+
+    /// The energy used since `previous` was read, in µJ
+    fn energy_since(&self, previous: u64) -> u64 {
+        match self.energy_uj >= previous {
+            true => self.energy_uj - previous,
+            false => self.max_energy_range_uj - previous + self.energy_uj,
+        }
+    }
+
+    fn is_package(&self) -> bool {
+        self.name.starts_with("package-")
+    }
+
+    fn is_dram(&self) -> bool {
+        self.name == "dram"
+    }
+}
+
+/// Power draw in watts, and the energy used since mltop started
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PowerSnapshot {
+    // all the CPU packages
+    pub cpu: Option<f32>,
+    pub dram: Option<f32>,
+    pub gpu: Option<f32>,
+    pub energy_wh: f64,
+}
+
+impl PowerSnapshot {
+    /// The power of everything we can measure, if anything
+    pub fn system(&self) -> Option<f32> {
+        let known: Vec<f32> = [self.cpu, self.dram, self.gpu]
+            .into_iter()
+            .flatten()
+            .collect();
+        match known.is_empty() {
+            true => None,
+            false => Some(known.iter().sum()),
+        }
+    }
+}
+
+/// Turns the RAPL energy counters and the GPU power into power figures,
+/// and adds up the energy used over the session
+pub struct PowerMeter {
+    sysfs: PathBuf,
+    // energy counter of each domain at the previous measurement
+    previous: HashMap<String, u64>,
+    clock: RateClock,
+    energy_wh: f64,
+}
+
+impl PowerMeter {
+    pub fn new(sysfs: &Path) -> PowerMeter {
+        PowerMeter {
+            sysfs: sysfs.to_path_buf(),
+            previous: HashMap::new(),
+            clock: RateClock::default(),
+            energy_wh: 0.0,
+        }
+    }
+
+    /// `gpu` is the current GPU power in watts, if there's a GPU
+    pub fn measure(&mut self, gpu: Option<f32>) -> Option<PowerSnapshot> {
+        let elapsed = self.clock.elapsed();
+        let domains = RaplDomain::read_all(&self.sysfs);
+        self.update(&domains, gpu, elapsed)
+    }
+
+    /// Like `measure`, with the domains already read and the `elapsed` of its clock.
+    /// `None` if there's nothing to measure.
+    pub fn update(
+        &mut self,
+        domains: &[RaplDomain],
+        gpu: Option<f32>,
+        elapsed: Option<f64>,
+    ) -> Option<PowerSnapshot> {
+        if domains.is_empty() && gpu.is_none() {
+            return None;
+        }
+
+        // (cpu, dram) energy in µJ since the previous measurement
+        let (mut cpu, mut dram) = (None, None);
+        for domain in domains {
+            let Some(previous) = self.previous.insert(domain.id.clone(), domain.energy_uj) else {
+                continue;
+            };
+            let energy = domain.energy_since(previous);
+            if domain.is_package() {
+                *cpu.get_or_insert(0) += energy;
+            } else if domain.is_dram() {
+                *dram.get_or_insert(0) += energy;
+            }
+        }
+
+        let Some(elapsed) = elapsed.filter(|e| *e > 0.0) else {
+            return Some(PowerSnapshot {
+                gpu,
+                energy_wh: self.energy_wh,
+                ..PowerSnapshot::default()
+            });
+        };
+        let watts = |uj: Option<u64>| uj.map(|uj| (uj as f64 / 1e6 / elapsed) as f32);
+        let snapshot = PowerSnapshot {
+            cpu: watts(cpu),
+            dram: watts(dram),
+            gpu,
+            energy_wh: 0.0,
+        };
+        self.energy_wh += snapshot.system().unwrap_or(0.0) as f64 * elapsed / JOULES_PER_WH;
+        Some(PowerSnapshot {
+            energy_wh: self.energy_wh,
+            ..snapshot
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PowerMeter, RaplDomain};
+    use std::path::{Path, PathBuf};
+
+    fn fixture() -> PathBuf {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs/rapl");
+        assert!(path.is_dir(), "missing fixture {:?}", path);
+        path
+    }
+
+    #[test]
+    fn test_read_domains() {
+        let domains = RaplDomain::read_all(&fixture());
+        let names: Vec<&str> = domains.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["package-0", "core", "dram"]);
+        assert_eq!(domains[0].energy_uj, 262_143_000_000);
+        assert!(RaplDomain::read_all(Path::new("/nonexistent")).is_empty());
+    }
+
+    #[test]
+    fn test_power_and_energy() {
+        let mut domains = RaplDomain::read_all(&fixture());
+        let mut meter = PowerMeter::new(&fixture());
+
+        let first = meter.update(&domains, Some(200.0), None).unwrap();
+        assert_eq!(first.cpu, None);
+        assert_eq!(first.system(), Some(200.0));
+
+        // the package counter wraps around, 2 s later
+        domains[0].energy_uj = 99_000_000;
+        domains[2].energy_uj += 20_000_000;
+        let second = meter.update(&domains, Some(200.0), Some(2.0)).unwrap();
+        assert_eq!(second.cpu, Some(50.0));
+        assert_eq!(second.dram, Some(10.0));
+        assert_eq!(second.system(), Some(260.0));
+        assert!((second.energy_wh - 520.0 / 3600.0).abs() < 1e-9);
+
+        assert_eq!(meter.update(&[], None, Some(1.0)), None);
+    }
+}
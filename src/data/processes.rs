@@ -1,8 +1,14 @@
-use nvml_wrapper::{error::NvmlError, Nvml};
-use std::collections::HashMap;
+use nvml_wrapper::enums::device::UsedGpuMemory;
+use nvml_wrapper::struct_wrappers::device::ProcessInfo;
+use nvml_wrapper::Nvml;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display};
-use sysinfo::System;
 use sysinfo::ThreadKind;
+use sysinfo::{System, Users};
+
+use super::cgroup::CgroupOwner;
+use super::framework::Framework;
+use super::procfs::{read_affinity, ProcStat};
 
 #[derive(Clone)]
 pub enum ProcessType {
@@ -37,6 +43,7 @@ impl Display for ProcessType {
 pub struct Process {
     pub pid: u32,
     pub type_: ProcessType,
+    pub user: String,
     pub command: String,
     // percentage 0-100% (can be higher than 100% if uses more than one core)
     pub cpu_usage: f32,
@@ -44,6 +51,30 @@ pub struct Process {
     pub memory: u64,
     // percentage 0-100%
     pub memory_usage: f32,
+    // bytes, summed over the GPUs
+    pub gpu_memory: u64,
+    // NVML indices of the GPUs the process runs on
+    pub gpus: Vec<u32>,
+    pub nice: i32,
+    // ids of the cpus the process is allowed to run on
+    pub affinity: Vec<usize>,
+    // the cpu the process last ran on
+    pub last_cpu: Option<usize>,
+    // bytes per second
+    pub read_rate: u64,
+    // bytes per second
+    pub write_rate: u64,
+    // single letter state, as in /proc/<pid>/stat
+    pub state: char,
+    // seconds since epoch
+    pub start_time: u64,
+    pub threads: usize,
+    // ML framework inferred from the loaded shared libraries
+    pub framework: Option<Framework>,
+    // container, slurm job or systemd unit the process runs in
+    pub container: Option<CgroupOwner>,
+    // pid of the process a user thread belongs to
+    pub owner: Option<u32>,
 }
 
 impl Process {
@@ -58,7 +89,16 @@ pub struct ProcessesSnapshot {
 }
 
 impl ProcessesSnapshot {
-    pub fn from_sysinfo_nvml(sys: &System, nvml: Option<&Nvml>) -> ProcessesSnapshot {
+    /// `elapsed` is the time in seconds since the previous process refresh,
+    /// used to turn the disk usage into rates. `None` on the first refresh.
+    /// `seen` are the processes of the previous refresh, by pid and start time.
+    pub fn from_sysinfo_nvml(
+        sys: &System,
+        users: &Users,
+        nvml: Option<&Nvml>,
+        elapsed: Option<f64>,
+        seen: &HashSet<(u32, u64)>,
+    ) -> ProcessesSnapshot {
         let total_memory = sys.total_memory();
 
         let mut processes: HashMap<u32, Process> = sys
@@ -67,6 +107,11 @@ impl ProcessesSnapshot {
             .filter_map(|(pid, p)| {
                 let pid = pid.as_u32();
                 let memory = p.memory();
+                let disk_usage = p.disk_usage();
+                let stat = ProcStat::read(pid);
+                // sysinfo reports the whole disk usage of a process as the delta the
+                // first time it sees it, so there's no rate until the next refresh
+                let elapsed = elapsed.filter(|_| seen.contains(&(pid, p.start_time())));
 
                 // TODO: Investigate why p.cmd() sometimes returns an empty array
                 let cmd_list = p.cmd();
@@ -85,6 +130,13 @@ impl ProcessesSnapshot {
                             },
                             None => ProcessType::Cpu,
                         },
+                        user: match p.user_id() {
+                            Some(uid) => match users.get_user_by_id(uid) {
+                                Some(u) => u.name().to_string(),
+                                None => (**uid).to_string(),
+                            },
+                            None => String::from("?"),
+                        },
                         command: cmd_list
                             .iter()
                             .filter_map(|s| s.to_str())
@@ -93,18 +145,45 @@ impl ProcessesSnapshot {
                         memory,
                         memory_usage: (memory as f32 / total_memory as f32) * 100.0,
                         cpu_usage: p.cpu_usage(),
+                        gpu_memory: 0,
+                        gpus: Vec::new(),
+                        nice: stat.as_ref().map(|s| s.nice).unwrap_or(0),
+                        affinity: read_affinity(pid).unwrap_or_default(),
+                        last_cpu: stat.as_ref().map(|s| s.processor),
+                        read_rate: _rate(disk_usage.read_bytes, elapsed),
+                        write_rate: _rate(disk_usage.written_bytes, elapsed),
+                        state: stat.as_ref().map(|s| s.state).unwrap_or('?'),
+                        start_time: p.start_time(),
+                        threads: stat.as_ref().map(|s| s.num_threads).unwrap_or(1),
+                        framework: None,
+                        container: None,
+                        owner: None,
                     },
                 ))
             })
             .collect();
 
+        // point the threads to the process they belong to
+        for (pid, p) in sys.processes() {
+            for task in p.tasks().into_iter().flatten().filter(|t| *t != pid) {
+                if let Some(thread) = processes.get_mut(&task.as_u32()) {
+                    thread.owner = Some(pid.as_u32());
+                }
+            }
+        }
+
         // find which ones are GPU and mark them as such
         if let Some(n) = nvml {
-            if let Ok(pids) = _gpu_compute_pids(n) {
-                _update_process_type(pids, &mut processes, ProcessType::GpuCompute)
-            }
-            if let Ok(pids) = _gpu_graphics_pids(n) {
-                _update_process_type(pids, &mut processes, ProcessType::GpuGraphic)
+            for index in 0..n.device_count().unwrap_or(0) {
+                let Ok(device) = n.device_by_index(index) else {
+                    continue;
+                };
+                if let Ok(infos) = device.running_compute_processes() {
+                    _update_gpu_processes(infos, index, &mut processes, ProcessType::GpuCompute)
+                }
+                if let Ok(infos) = device.running_graphics_processes() {
+                    _update_gpu_processes(infos, index, &mut processes, ProcessType::GpuGraphic)
+                }
             }
         }
 
@@ -114,32 +193,29 @@ impl ProcessesSnapshot {
     }
 }
 
-fn _gpu_compute_pids(nvml: &Nvml) -> Result<Vec<u32>, NvmlError> {
-    let device = nvml.device_by_index(0)?;
-    Ok(device
-        .running_compute_processes()?
-        .iter()
-        .map(|pi| pi.pid)
-        .collect())
-}
-
-fn _gpu_graphics_pids(nvml: &Nvml) -> Result<Vec<u32>, NvmlError> {
-    let device = nvml.device_by_index(0)?;
-    Ok(device
-        .running_graphics_processes()?
-        .iter()
-        .map(|pi| pi.pid)
-        .collect())
+fn _rate(delta: u64, elapsed: Option<f64>) -> u64 {
+    match elapsed {
+        Some(secs) if secs > 0.0 => (delta as f64 / secs) as u64,
+        _ => 0,
+    }
 }
 
-fn _update_process_type(
-    pids: Vec<u32>,
+fn _update_gpu_processes(
+    infos: Vec<ProcessInfo>,
+    gpu: u32,
     processes: &mut HashMap<u32, Process>,
     process_type: ProcessType,
 ) {
-    for pid in pids {
-        if let Some(obj) = processes.get_mut(&pid) {
+    for info in infos {
+        if let Some(obj) = processes.get_mut(&info.pid) {
             obj.type_ = process_type.clone();
+            // a process shows up in both lists when it does compute and graphics
+            if !obj.gpus.contains(&gpu) {
+                obj.gpus.push(gpu);
+                if let UsedGpuMemory::Used(bytes) = info.used_gpu_memory {
+                    obj.gpu_memory += bytes;
+                }
+            }
         }
     }
 }
@@ -0,0 +1,74 @@
+use std::fs::read_to_string;
+
+use crate::utils::parse_cpu_list;
+
+/// The fields we use from `/proc/<pid>/stat`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcStat {
+    // single letter state, e.g. 'R' for running or 'S' for sleeping
+    pub state: char,
+    pub nice: i32,
+    pub num_threads: usize,
+    // the cpu the process last ran on
+    pub processor: usize,
+}
+
+impl ProcStat {
+    pub fn read(pid: u32) -> Option<ProcStat> {
+        Self::parse(&read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+    }
+
+    pub fn parse(contents: &str) -> Option<ProcStat> {
+        // the command (2nd field) is wrapped in parentheses and may contain spaces,
+        // so we start splitting after the last closing parenthesis (3rd field onwards)
+        let (_, rest) = contents.rsplit_once(')')?;
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        Some(ProcStat {
+            state: fields.first()?.chars().next()?,
+            nice: fields.get(16)?.parse().ok()?,
+            num_threads: fields.get(17)?.parse().ok()?,
+            processor: fields.get(36)?.parse().ok()?,
+        })
+    }
+}
+
+/// Reads the list of cpus a process is allowed to run on from `/proc/<pid>/status`
+pub fn read_affinity(pid: u32) -> Option<Vec<usize>> {
+    parse_affinity(&read_to_string(format!("/proc/{}/status", pid)).ok()?)
+}
+
+pub fn parse_affinity(status: &str) -> Option<Vec<usize>> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("Cpus_allowed_list:"))
+        .and_then(parse_cpu_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_affinity, ProcStat};
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "1234 (python train.py) S 1 1234 1234 0 -1 4194560 2000 0 0 0 \
+                    150 20 0 0 25 5 17 0 100 0 0 18446744073709551615 0 0 0 0 0 0 0 \
+                    0 0 0 0 0 17 3 0 0 0 0 0";
+        assert_eq!(
+            ProcStat::parse(stat),
+            Some(ProcStat {
+                state: 'S',
+                nice: 5,
+                num_threads: 17,
+                processor: 3,
+            })
+        );
+        assert_eq!(ProcStat::parse("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_affinity() {
+        let status = "Name:\tpython\nCpus_allowed:\tff\nCpus_allowed_list:\t0-3,6\n";
+        assert_eq!(parse_affinity(status), Some(vec![0, 1, 2, 3, 6]));
+        assert_eq!(parse_affinity("Name:\tpython\n"), None);
+    }
+}
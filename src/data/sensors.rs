@@ -0,0 +1,76 @@
+use sysinfo::Components;
+
+/// A temperature sensor, e.g. an NVMe drive or the chipset. Temperatures in °C.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorSnapshot {
+    pub label: String,
+    pub temperature: Option<f32>,
+    // the highest temperature seen since mltop started
+    pub max: Option<f32>,
+    pub critical: Option<f32>,
+}
+
+impl SensorSnapshot {
+    /// How close the sensor is to its critical temperature, 0 - 1.
+    /// `None` if the sensor doesn't report one.
+    pub fn closeness(&self) -> Option<f32> {
+        let critical = self.critical.filter(|c| *c > 0.0)?;
+        Some(self.temperature? / critical)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SensorsSnapshot {
+    pub sensors: Vec<SensorSnapshot>,
+}
+
+impl SensorsSnapshot {
+    pub fn from_components(components: &Components) -> SensorsSnapshot {
+        let mut sensors: Vec<SensorSnapshot> = components
+            .list()
+            .iter()
+            .filter(|c| !is_core_sensor(c.label()))
+            .map(|c| SensorSnapshot {
+                label: c.label().to_string(),
+                temperature: c.temperature(),
+                max: c.max(),
+                critical: c.critical(),
+            })
+            .collect();
+        sensors.sort_by(|a, b| a.label.cmp(&b.label));
+        SensorsSnapshot { sensors }
+    }
+}
+
+// the per core sensors of coretemp, e.g. "coretemp Core 12", are already shown
+// in the CPU panel, and there can be hundreds of them
+fn is_core_sensor(label: &str) -> bool {
+    label
+        .split_once("Core ")
+        .is_some_and(|(_, id)| id.parse::<usize>().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_core_sensor, SensorSnapshot};
+
+    #[test]
+    fn test_core_sensor() {
+        assert!(is_core_sensor("coretemp Core 12"));
+        assert!(!is_core_sensor("coretemp Package id 0"));
+        assert!(!is_core_sensor("nvme Composite Samsung SSD 990 PRO 2TB"));
+    }
+
+    #[test]
+    fn test_closeness() {
+        let mut sensor = SensorSnapshot {
+            label: "nvme Composite".to_string(),
+            temperature: Some(63.0),
+            max: Some(70.0),
+            critical: Some(84.0),
+        };
+        assert_eq!(sensor.closeness(), Some(0.75));
+        sensor.critical = None;
+        assert_eq!(sensor.closeness(), None);
+    }
+}
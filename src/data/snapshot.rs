@@ -1,7 +1,15 @@
+use super::cgroup_limits::CgroupLimits;
 use super::cpu::CpuSnapshot;
+use super::disk::DisksSnapshot;
+use super::filesystem::FilesystemsSnapshot;
 use super::gpu::GpuSnapshot;
+use super::host::HostSnapshot;
 use super::memory::MemorySnapshot;
+use super::network::NetworkSnapshot;
+use super::numa::NumaSnapshot;
+use super::power::PowerSnapshot;
 use super::processes::ProcessesSnapshot;
+use super::sensors::SensorsSnapshot;
 
 #[derive(Clone)]
 pub struct DataSnapshot {
@@ -9,4 +17,12 @@ pub struct DataSnapshot {
     pub memory: Option<MemorySnapshot>,
     pub gpu: Option<GpuSnapshot>,
     pub processes: Option<ProcessesSnapshot>,
+    pub power: Option<PowerSnapshot>,
+    pub disks: Option<DisksSnapshot>,
+    pub network: Option<NetworkSnapshot>,
+    pub filesystems: Option<FilesystemsSnapshot>,
+    pub host: Option<HostSnapshot>,
+    pub numa: Option<NumaSnapshot>,
+    pub sensors: Option<SensorsSnapshot>,
+    pub cgroup: Option<CgroupLimits>,
 }
@@ -1,17 +1,37 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::processes::ProcessesSnapshot;
 use super::snapshot::DataSnapshot;
-use crate::config::MAX_STORED_SNAPSHOTS;
+use crate::config::{
+    MemoryLeakConfig, GRAPH_X_AXIS_WINDOW_IN_SECONDS, MAX_STORED_PROCESS_SNAPSHOTS,
+};
+use crate::constants::BYTES_PER_MB;
+
+// the resident memory samples must span this fraction of the leak window
+const LEAK_MIN_COVERAGE: f64 = 0.9;
+// and fit a straight line at least this well (R²), so a one-off jump isn't a leak
+const LEAK_MIN_FIT: f64 = 0.8;
+// the leak window keeps at most one resident memory sample per this fraction of it
+const LEAK_BUCKETS: u32 = 60;
 
 // Structure for storing only what we need to track
 pub struct StoredSnapshot {
+    // when it was taken, since the refresh rate can change
+    pub time: Instant,
     pub cpu_use: f32,
     // memory use in percentage 0 - 1 (includes swap)
     pub mem_use: f64,
     pub gpu_use: Option<u32>,
     pub gpu_mem_use: Option<u64>,
+    // utilization 0-100% of the busiest disk
+    pub disk_use: Option<f32>,
+    // utilization 0-100% of the link speed of the busiest network interface
+    pub net_use: Option<f32>,
 }
 
 impl StoredSnapshot {
-    pub fn from_data_snapshot(snapshot: &DataSnapshot) -> StoredSnapshot {
+    pub fn from_data_snapshot(snapshot: &DataSnapshot, time: Instant) -> StoredSnapshot {
         let (gpu_use, gpu_mem_use) = snapshot
             .gpu
             .as_ref()
@@ -19,17 +39,170 @@ impl StoredSnapshot {
             .unzip();
 
         StoredSnapshot {
+            time,
             cpu_use: snapshot.cpu.as_ref().unwrap().usage,
             mem_use: snapshot.memory.as_ref().unwrap().total_percent(),
             gpu_use,
             gpu_mem_use,
+            disk_use: snapshot.disks.as_ref().and_then(|d| d.max_utilization()),
+            net_use: snapshot.network.as_ref().and_then(|n| n.max_utilization()),
+        }
+    }
+}
+
+/// Recent usage of a single process, oldest first
+#[derive(Default)]
+pub struct ProcessHistory {
+    // used to detect pids that got reused by a new process
+    start_time: u64,
+    pub cpu_use: VecDeque<f32>,
+    // bytes
+    pub gpu_mem_use: VecDeque<u64>,
+    // the cpu the process last ran on, at each refresh
+    pub last_cpus: VecDeque<Option<usize>>,
+    // resident memory over the leak window
+    memory_trend: MemoryTrend,
+    // bytes per second, when the resident memory grew steadily over the leak window
+    pub memory_growth: Option<f64>,
+}
+
+impl ProcessHistory {
+    fn new(start_time: u64) -> ProcessHistory {
+        ProcessHistory {
+            start_time,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, cpu_use: f32, gpu_mem_use: u64, last_cpu: Option<usize>) {
+        self.cpu_use.push_back(cpu_use);
+        self.gpu_mem_use.push_back(gpu_mem_use);
+        self.last_cpus.push_back(last_cpu);
+        if self.cpu_use.len() > MAX_STORED_PROCESS_SNAPSHOTS {
+            self.cpu_use.pop_front();
+            self.gpu_mem_use.pop_front();
+            self.last_cpus.pop_front();
+        }
+    }
+
+    /// Whether the process ran on none of `cpus` at each of the last `samples` refreshes
+    pub fn ran_outside(&self, cpus: &[usize], samples: usize) -> bool {
+        self.last_cpus.len() >= samples
+            && self
+                .last_cpus
+                .iter()
+                .rev()
+                .take(samples)
+                .all(|cpu| cpu.is_some_and(|c| !cpus.contains(&c)))
+    }
+
+    fn push_memory(&mut self, at: Instant, memory: u64, window: Duration) {
+        self.memory_trend.push(at, memory, window);
+    }
+
+    /// The growth of the resident memory in bytes per second, if it grew by at least
+    /// `min_growth` for about the whole window
+    fn memory_growth(&self, window: Duration, min_growth: f64) -> Option<f64> {
+        if self.memory_trend.span() < window.as_secs_f64() * LEAK_MIN_COVERAGE {
+            return None;
+        }
+        let (slope, fit) = self.memory_trend.fit()?;
+        // a shrinking process isn't leaking, even with a `min_growth` of 0
+        (slope > 0.0 && slope >= min_growth && fit >= LEAK_MIN_FIT).then_some(slope)
+    }
+}
+
+/// Samples of the resident memory over the leak window, at most one per bucket, with
+/// the running sums of a least squares fit so that no refresh has to go over them
+#[derive(Default)]
+struct MemoryTrend {
+    // the time and memory the samples are relative to, to keep the sums small
+    origin: Option<(Instant, u64)>,
+    // (seconds, bytes) since the origin, oldest first
+    samples: VecDeque<(f64, f64)>,
+    // n, Σt, Σm, Σt², Σtm, Σm²
+    sums: [f64; 6],
+}
+
+impl MemoryTrend {
+    fn push(&mut self, at: Instant, memory: u64, window: Duration) {
+        let (start, base) = *self.origin.get_or_insert((at, memory));
+        let t = at.duration_since(start).as_secs_f64();
+        let bucket = window.as_secs_f64() / LEAK_BUCKETS as f64;
+        if self
+            .samples
+            .back()
+            .is_some_and(|(last, _)| t - last < bucket)
+        {
+            return;
+        }
+        let sample = (t, memory as f64 - base as f64);
+        self.samples.push_back(sample);
+        self.add(sample, 1.0);
+        while let Some(&first) = self
+            .samples
+            .front()
+            .filter(|(first, _)| t - first > window.as_secs_f64())
+        {
+            self.samples.pop_front();
+            self.add(first, -1.0);
+        }
+    }
+
+    // adds (or removes with a `sign` of -1) a sample to the sums
+    fn add(&mut self, (t, m): (f64, f64), sign: f64) {
+        for (sum, value) in self.sums.iter_mut().zip([1.0, t, m, t * t, t * m, m * m]) {
+            *sum += sign * value;
+        }
+    }
+
+    // seconds between the first and last samples
+    fn span(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((first, _)), Some((last, _))) => last - first,
+            _ => 0.0,
         }
     }
+
+    /// The line through the samples, as (slope in bytes per second, R²)
+    fn fit(&self) -> Option<(f64, f64)> {
+        let [n, t, m, tt, tm, mm] = self.sums;
+        let sxx = tt - t * t / n;
+        let sxy = tm - t * m / n;
+        let syy = mm - m * m / n;
+        if n < 2.0 || sxx <= 0.0 || syy <= 0.0 {
+            return None;
+        }
+        Some((sxy / sxx, sxy * sxy / (sxx * syy)))
+    }
+}
+
+/// Remembers which processes are leaking memory, so we only warn once when one starts to
+#[derive(Default)]
+pub struct LeakWatch {
+    leaking: HashSet<u32>,
+}
+
+impl LeakWatch {
+    /// The pids whose memory started growing since the previous check
+    pub fn check(&mut self, histories: &HashMap<u32, ProcessHistory>) -> Vec<u32> {
+        let leaking: HashSet<u32> = histories
+            .iter()
+            .filter(|(_, h)| h.memory_growth.is_some())
+            .map(|(pid, _)| *pid)
+            .collect();
+        let mut started: Vec<u32> = leaking.difference(&self.leaking).copied().collect();
+        started.sort_unstable();
+        self.leaking = leaking;
+        started
+    }
 }
 
 #[derive(Default)]
 pub struct DataStore {
     pub snapshots: Vec<StoredSnapshot>,
+    // per pid
+    pub processes: HashMap<u32, ProcessHistory>,
 }
 
 impl DataStore {
@@ -37,10 +210,152 @@ impl DataStore {
         DataStore::default()
     }
 
+    /// Stores a snapshot, and drops the ones that are too old for the graph
     pub fn save(&mut self, snapshot: StoredSnapshot) {
+        let window = Duration::from_secs(GRAPH_X_AXIS_WINDOW_IN_SECONDS);
+        let now = snapshot.time;
         self.snapshots.push(snapshot);
-        if self.snapshots.len() > MAX_STORED_SNAPSHOTS {
-            self.snapshots.remove(0);
+        let expired = self
+            .snapshots
+            .iter()
+            .take_while(|s| now.duration_since(s.time) > window)
+            .count();
+        self.snapshots.drain(..expired);
+    }
+
+    /// Appends the usage of every process to its history, updates their memory growth
+    /// and drops the history of the processes that exited
+    pub fn save_processes(
+        &mut self,
+        snapshot: &ProcessesSnapshot,
+        leak: &MemoryLeakConfig,
+        now: Instant,
+    ) {
+        let window = Duration::from_secs(leak.window);
+        let min_growth = leak.min_growth_mib_per_hour * BYTES_PER_MB as f64 / 3600.0;
+        let mut histories = HashMap::with_capacity(snapshot.processes.len());
+        for p in &snapshot.processes {
+            let mut history = match self.processes.remove(&p.pid) {
+                Some(h) if h.start_time == p.start_time => h,
+                _ => ProcessHistory::new(p.start_time),
+            };
+            history.push(p.cpu_usage, p.gpu_memory, p.last_cpu);
+            // threads share the memory of their process
+            if !p.is_thread() {
+                history.push_memory(now, p.memory, window);
+                history.memory_growth = history.memory_growth(window, min_growth);
+            }
+            histories.insert(p.pid, history);
+        }
+        self.processes = histories;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataStore, LeakWatch, ProcessHistory, StoredSnapshot};
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    const WINDOW: Duration = Duration::from_secs(600);
+
+    fn history(memory: impl Fn(u64) -> u64) -> ProcessHistory {
+        let start = Instant::now();
+        let mut history = ProcessHistory::new(0);
+        for s in 0..=600 {
+            history.push_memory(start + Duration::from_secs(s), memory(s), WINDOW);
         }
+        history
+    }
+
+    #[test]
+    fn test_memory_growth() {
+        // 1 MB/s with some noise
+        let leaking = history(|s| 1_000_000_000 + s * 1_000_000 + (s % 7) * 100_000);
+        let growth = leaking.memory_growth(WINDOW, 1000.0).unwrap();
+        assert!((growth - 1_000_000.0).abs() < 10_000.0);
+        // slower than the threshold
+        assert_eq!(leaking.memory_growth(WINDOW, 2_000_000.0), None);
+        // one sample per 10 seconds of the window
+        assert_eq!(leaking.memory_trend.samples.len(), 61);
+
+        // a single allocation half way isn't a steady growth
+        let jump = history(|s| if s < 300 { 1_000 } else { 500_000_000 });
+        assert_eq!(jump.memory_growth(WINDOW, 1000.0), None);
+        let flat = history(|_| 1_000);
+        assert_eq!(flat.memory_growth(WINDOW, 1000.0), None);
+        // shrinking isn't leaking, whatever the threshold
+        let shrinking = history(|s| 1_000_000_000 - s * 1_000_000);
+        assert_eq!(shrinking.memory_growth(WINDOW, 0.0), None);
+        assert_eq!(shrinking.memory_growth(WINDOW, -1000.0), None);
+
+        // not enough history yet
+        let mut young = ProcessHistory::new(0);
+        let start = Instant::now();
+        for s in 0..60 {
+            young.push_memory(start + Duration::from_secs(s), s * 1_000_000, WINDOW);
+        }
+        assert_eq!(young.memory_growth(WINDOW, 1000.0), None);
+    }
+
+    #[test]
+    fn test_ran_outside() {
+        let mut history = ProcessHistory::new(0);
+        for cpu in [Some(0), Some(4), None, Some(5), Some(6)] {
+            history.push(0.0, 0, cpu);
+        }
+        assert!(history.ran_outside(&[0, 1], 2));
+        // unknown cpus don't count as outside
+        assert!(!history.ran_outside(&[0, 1], 3));
+        assert!(!history.ran_outside(&[0, 1, 6], 2));
+        // not enough samples yet
+        assert!(!history.ran_outside(&[0, 1], 6));
+    }
+
+    #[test]
+    fn test_memory_trend_eviction() {
+        // 4 refreshes a second for half an hour, growing 1 MB/s for the last 10 minutes
+        let start = Instant::now();
+        let mut history = ProcessHistory::new(0);
+        for i in 0..(4 * 1800u64) {
+            let s = i / 4;
+            let memory = 1_000_000_000 + s.saturating_sub(1200) * 1_000_000;
+            history.push_memory(start + Duration::from_millis(i * 250), memory, WINDOW);
+        }
+        assert!(history.memory_trend.samples.len() <= 61);
+        // the flat part was evicted from the sums too
+        let growth = history.memory_growth(WINDOW, 1000.0).unwrap();
+        assert!((growth - 1_000_000.0).abs() < 10_000.0);
+    }
+
+    #[test]
+    fn test_save_drops_old_snapshots() {
+        let start = Instant::now();
+        let mut store = DataStore::new();
+        // one snapshot every 500ms for 3 minutes
+        for i in 0..360 {
+            store.save(StoredSnapshot {
+                time: start + Duration::from_millis(i * 500),
+                cpu_use: 0.0,
+                mem_use: 0.0,
+                gpu_use: None,
+                gpu_mem_use: None,
+                disk_use: None,
+                net_use: None,
+            });
+        }
+        // the last 120 seconds, both ends included
+        assert_eq!(store.snapshots.len(), 241);
+    }
+
+    #[test]
+    fn test_leak_watch() {
+        let mut watch = LeakWatch::default();
+        let mut histories =
+            HashMap::from([(1, ProcessHistory::new(0)), (2, ProcessHistory::new(0))]);
+        assert!(watch.check(&histories).is_empty());
+        histories.get_mut(&2).unwrap().memory_growth = Some(1000.0);
+        assert_eq!(watch.check(&histories), [2]);
+        assert!(watch.check(&histories).is_empty());
     }
 }
@@ -1,7 +1,15 @@
+use super::cgroup_limits::CgroupLimits;
 use super::cpu::CpuSnapshot;
+use super::disk::DisksSnapshot;
+use super::filesystem::FilesystemsSnapshot;
 use super::gpu::GpuSnapshot;
+use super::host::HostSnapshot;
 use super::memory::MemorySnapshot;
+use super::network::NetworkSnapshot;
+use super::numa::NumaSnapshot;
+use super::power::PowerSnapshot;
 use super::processes::ProcessesSnapshot;
+use super::sensors::SensorsSnapshot;
 use super::snapshot::DataSnapshot;
 
 /// The main data struct used by the widgets to render information.
@@ -11,6 +19,15 @@ pub struct SystemData {
     pub memory: MemorySnapshot,
     pub gpu: Option<GpuSnapshot>,
     pub processes: ProcessesSnapshot,
+    pub power: Option<PowerSnapshot>,
+    pub disks: Option<DisksSnapshot>,
+    pub network: Option<NetworkSnapshot>,
+    pub filesystems: Option<FilesystemsSnapshot>,
+    pub host: Option<HostSnapshot>,
+    pub numa: Option<NumaSnapshot>,
+    pub sensors: Option<SensorsSnapshot>,
+    // limits of the container or job mltop runs in
+    pub cgroup: Option<CgroupLimits>,
 }
 
 impl SystemData {
@@ -22,6 +39,14 @@ impl SystemData {
             processes: snapshot
                 .processes
                 .expect("First snapshot must have processes"),
+            power: snapshot.power,
+            disks: snapshot.disks,
+            network: snapshot.network,
+            filesystems: snapshot.filesystems,
+            host: snapshot.host,
+            numa: snapshot.numa,
+            sensors: snapshot.sensors,
+            cgroup: snapshot.cgroup,
         }
     }
 
@@ -38,9 +63,38 @@ impl SystemData {
         if let Some(p) = snapshot.processes {
             self.processes = p;
         }
+        if let Some(p) = snapshot.power {
+            self.power = Some(p);
+        }
+        if let Some(d) = snapshot.disks {
+            self.disks = Some(d);
+        }
+        if let Some(n) = snapshot.network {
+            self.network = Some(n);
+        }
+        if let Some(f) = snapshot.filesystems {
+            self.filesystems = Some(f);
+        }
+        if let Some(h) = snapshot.host {
+            self.host = Some(h);
+        }
+        if let Some(n) = snapshot.numa {
+            self.numa = Some(n);
+        }
+        if let Some(s) = snapshot.sensors {
+            self.sensors = Some(s);
+        }
+        if let Some(c) = snapshot.cgroup {
+            self.cgroup = Some(c);
+        }
     }
 
     pub fn has_gpu(&self) -> bool {
         self.gpu.is_some()
     }
+
+    /// The NUMA nodes and the node of each GPU, on machines with more than one node
+    pub fn gpu_numa(&self) -> Option<&NumaSnapshot> {
+        self.numa.as_ref().filter(|n| n.is_numa())
+    }
 }
@@ -0,0 +1,321 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::constants::SYSFS_ROOT;
+use crate::utils::parse_cpu_list;
+
+/// Where a logical cpu sits, from `/sys/devices/system/cpu/cpu<N>/topology`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoreTopology {
+    pub package: usize,
+    pub die: usize,
+    // physical core id, shared by hyperthreads. Not necessarily contiguous
+    pub core: usize,
+    // the first cpu sharing its L3 cache, from `cache/index3/shared_cpu_list`
+    pub l3: Option<usize>,
+}
+
+/// The ids of the online cpus, from `/sys/devices/system/cpu/online`
+pub fn online_cpus(sysfs: &Path) -> Option<Vec<usize>> {
+    parse_cpu_list(&read_to_string(sysfs.join("devices/system/cpu/online")).ok()?)
+}
+
+/// The topology of every logical cpu, indexed by cpu id
+#[derive(Default)]
+pub struct CpuTopology {
+    cpus: Vec<Option<CoreTopology>>,
+}
+
+impl CpuTopology {
+    pub fn read() -> CpuTopology {
+        Self::read_from(Path::new(SYSFS_ROOT))
+    }
+
+    pub fn read_from(sysfs: &Path) -> CpuTopology {
+        let mut cpus = Vec::new();
+        let Ok(entries) = read_dir(sysfs.join("devices/system/cpu")) else {
+            return CpuTopology { cpus };
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name();
+            let Some(id) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("cpu"))
+                .and_then(|n| n.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            if cpus.len() <= id {
+                cpus.resize(id + 1, None);
+            }
+            cpus[id] = Self::read_core(&entry.path());
+        }
+        CpuTopology { cpus }
+    }
+
+    fn read_core(dir: &Path) -> Option<CoreTopology> {
+        let read = |file: &str| -> Option<usize> {
+            read_to_string(dir.join(file)).ok()?.trim().parse().ok()
+        };
+        Some(CoreTopology {
+            package: read("topology/physical_package_id")?,
+            // older kernels don't have dies
+            die: read("topology/die_id").unwrap_or(0),
+            core: read("topology/core_id")?,
+            l3: match read("cache/index3/level") {
+                Some(3) => read_to_string(dir.join("cache/index3/shared_cpu_list"))
+                    .ok()
+                    .and_then(|l| parse_cpu_list(&l))
+                    .and_then(|cpus| cpus.into_iter().min()),
+                _ => None,
+            },
+        })
+    }
+
+    pub fn get(&self, cpu: usize) -> Option<CoreTopology> {
+        self.cpus.get(cpu).copied().flatten()
+    }
+
+    /// Which of the `ccd_count` CCDs of its package `core` is on. The CCDs aren't
+    /// in the topology, but the cores of a CCD share an L3 cache (Zen 2 has two
+    /// per CCD, one per CCX), so the L3 caches are split between the CCDs. When
+    /// they're unknown or don't add up, the cores are split evenly, since they're
+    /// numbered in order (and grouped by die). That's wrong with cores disabled.
+    fn ccd_of(&self, core: &CoreTopology, ccd_count: usize) -> usize {
+        let in_package = || {
+            self.cpus
+                .iter()
+                .flatten()
+                .filter(|c| c.package == core.package)
+        };
+        let caches: BTreeSet<usize> = in_package().filter_map(|c| c.l3).collect();
+        if let Some(l3) = core.l3.filter(|_| caches.len().is_multiple_of(ccd_count)) {
+            let rank = caches.iter().position(|c| *c == l3).unwrap_or(0);
+            return rank * ccd_count / caches.len();
+        }
+        let cores: BTreeSet<(usize, usize)> = in_package().map(|c| (c.die, c.core)).collect();
+        let rank = cores
+            .iter()
+            .position(|c| *c == (core.die, core.core))
+            .unwrap_or(0);
+        rank * ccd_count / cores.len().max(1)
+    }
+}
+
+/// CPU temperatures in °C, from the coretemp (Intel) and k10temp (AMD) hwmon drivers
+#[derive(Debug, Default, PartialEq)]
+pub struct CpuTemperatures {
+    // (package, physical core id), from coretemp's "Core N"
+    pub cores: HashMap<(usize, usize), f32>,
+    // (package, ccd), from k10temp's "TccdN"
+    pub ccds: HashMap<(usize, usize), f32>,
+    // from coretemp's "Package id N" or k10temp's "Tdie"/"Tctl"
+    pub packages: HashMap<usize, f32>,
+}
+
+/// The coretemp and k10temp hwmon devices, looked up once: reading the temperatures
+/// of every hwmon device on each refresh would also wake up drives and the like
+#[derive(Default)]
+pub struct CpuHwmons {
+    // (driver name, hwmon dir), in hwmon order
+    dirs: Vec<(String, PathBuf)>,
+}
+
+impl CpuHwmons {
+    pub fn find() -> CpuHwmons {
+        Self::find_in(Path::new(SYSFS_ROOT))
+    }
+
+    pub fn find_in(sysfs: &Path) -> CpuHwmons {
+        let mut hwmons: Vec<PathBuf> = match read_dir(sysfs.join("class/hwmon")) {
+            Ok(entries) => entries.filter_map(|e| Some(e.ok()?.path())).collect(),
+            Err(_) => Vec::new(),
+        };
+        // hwmon10 comes after hwmon9, and the order of the k10temp ones gives the package
+        hwmons.sort_by_key(|p| {
+            let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            (name.len(), name.to_string())
+        });
+
+        let dirs = hwmons
+            .into_iter()
+            .filter_map(|dir| {
+                let name = read_to_string(dir.join("name")).ok()?.trim().to_string();
+                matches!(name.as_str(), "coretemp" | "k10temp").then_some((name, dir))
+            })
+            .collect();
+        CpuHwmons { dirs }
+    }
+
+    pub fn read(&self) -> CpuTemperatures {
+        let sensors: Vec<(String, Vec<(String, f32)>)> = self
+            .dirs
+            .iter()
+            .map(|(name, dir)| (name.clone(), CpuTemperatures::read_hwmon_temps(dir)))
+            .collect();
+        CpuTemperatures::from_sensors(&sensors)
+    }
+}
+
+impl CpuTemperatures {
+    pub fn read_from(sysfs: &Path) -> CpuTemperatures {
+        CpuHwmons::find_in(sysfs).read()
+    }
+
+    // (label, °C) of the temp<N>_input files of a hwmon device
+    fn read_hwmon_temps(dir: &Path) -> Vec<(String, f32)> {
+        let Ok(entries) = read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|e| {
+                let file = e.ok()?.file_name().to_str()?.to_string();
+                let sensor = file
+                    .strip_suffix("_input")
+                    .filter(|s| s.starts_with("temp"))?;
+                let millidegrees: f32 =
+                    read_to_string(dir.join(&file)).ok()?.trim().parse().ok()?;
+                let label = read_to_string(dir.join(format!("{}_label", sensor)))
+                    .map(|l| l.trim().to_string())
+                    .unwrap_or_else(|_| sensor.to_string());
+                Some((label, millidegrees / 1000.0))
+            })
+            .collect()
+    }
+
+    /// Builds the temperatures from the (driver name, [(label, °C)]) of each hwmon device,
+    /// in hwmon order
+    pub fn from_sensors(sensors: &[(String, Vec<(String, f32)>)]) -> CpuTemperatures {
+        let mut temperatures = CpuTemperatures::default();
+        let (mut coretemps, mut k10temps) = (0, 0);
+        for (name, temps) in sensors {
+            match name.as_str() {
+                "coretemp" => {
+                    // there's one coretemp device per package, which reports its id
+                    let package = temps
+                        .iter()
+                        .find_map(|(l, _)| l.strip_prefix("Package id ")?.parse().ok())
+                        .unwrap_or(coretemps);
+                    coretemps += 1;
+                    for (label, t) in temps {
+                        if label.starts_with("Package id ") {
+                            temperatures.packages.insert(package, *t);
+                        } else if let Some(core) = label.strip_prefix("Core ") {
+                            if let Ok(core) = core.parse() {
+                                temperatures.cores.insert((package, core), *t);
+                            }
+                        }
+                    }
+                }
+                "k10temp" | "zenpower" => {
+                    let package = k10temps;
+                    k10temps += 1;
+                    // Tdie is Tctl without the offset some CPUs add for the fans
+                    let tdie = temps.iter().find(|(l, _)| l == "Tdie");
+                    let tctl = temps.iter().find(|(l, _)| l == "Tctl");
+                    if let Some((_, t)) = tdie.or(tctl) {
+                        temperatures.packages.insert(package, *t);
+                    }
+                    for (label, t) in temps {
+                        let ccd = label
+                            .strip_prefix("Tccd")
+                            .and_then(|n| n.parse::<usize>().ok());
+                        if let Some(ccd) = ccd.filter(|n| *n > 0) {
+                            temperatures.ccds.insert((package, ccd - 1), *t);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        temperatures
+    }
+
+    /// The temperature of each of the first `n` logical cpus: the one of its physical
+    /// core if there's one, otherwise the one of its CCD or its package. 0 if unknown.
+    pub fn per_cpu(&self, topology: &CpuTopology, n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|cpu| {
+                let Some(c) = topology.get(cpu) else {
+                    return 0.0;
+                };
+                if let Some(t) = self.cores.get(&(c.package, c.core)) {
+                    return *t;
+                }
+                let ccd_count = self.ccds.keys().filter(|(p, _)| *p == c.package).count();
+                if ccd_count > 0 {
+                    let ccd = topology.ccd_of(&c, ccd_count);
+                    if let Some(t) = self.ccds.get(&(c.package, ccd)) {
+                        return *t;
+                    }
+                }
+                self.packages.get(&c.package).copied().unwrap_or(0.0)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CpuHwmons, CpuTemperatures, CpuTopology};
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/sysfs")
+            .join(name);
+        assert!(path.is_dir(), "missing fixture {:?}", path);
+        path
+    }
+
+    #[test]
+    fn test_intel_hyperthreads() {
+        // 2 cores with ids 0 and 4, cpus 2 and 3 are their hyperthreads
+        let root = fixture("intel");
+        let topology = CpuTopology::read_from(&root);
+        assert_eq!(topology.get(3).map(|c| c.core), Some(4));
+
+        let temperatures = CpuTemperatures::read_from(&root);
+        assert_eq!(temperatures.packages.get(&0), Some(&55.0));
+        assert_eq!(temperatures.per_cpu(&topology, 4), [50.0, 60.0, 50.0, 60.0]);
+    }
+
+    #[test]
+    fn test_amd_ccds() {
+        // 4 cores on 2 CCDs, cpus 4-7 are the hyperthreads of cpus 0-3
+        let root = fixture("amd");
+        let topology = CpuTopology::read_from(&root);
+        // the nvme drive's sensor is left alone
+        let hwmons = CpuHwmons::find_in(&root);
+        assert_eq!(hwmons.dirs.len(), 1);
+        assert_eq!(hwmons.dirs[0].0, "k10temp");
+        let temperatures = CpuTemperatures::read_from(&root);
+        assert_eq!(
+            temperatures.per_cpu(&topology, 8),
+            [65.0, 65.0, 75.0, 75.0, 65.0, 65.0, 75.0, 75.0]
+        );
+
+        // without the CCD sensors we fall back to the package temperature
+        let tctl = CpuTemperatures::from_sensors(&[(
+            String::from("k10temp"),
+            vec![(String::from("Tctl"), 70.0)],
+        )]);
+        assert_eq!(tctl.per_cpu(&topology, 2), [70.0, 70.0]);
+    }
+
+    #[test]
+    fn test_amd_l3_caches() {
+        // 7 cores, with one disabled on the first CCD: cpus 0-2 share an L3 cache
+        // and cpus 3-6 another, which splitting the cores evenly would get wrong
+        let root = fixture("amd_uneven");
+        let topology = CpuTopology::read_from(&root);
+        assert_eq!(topology.get(4).and_then(|c| c.l3), Some(3));
+
+        let temperatures = CpuTemperatures::read_from(&root);
+        assert_eq!(
+            temperatures.per_cpu(&topology, 7),
+            [65.0, 65.0, 65.0, 75.0, 75.0, 75.0, 75.0]
+        );
+    }
+}
@@ -2,7 +2,10 @@ pub struct DataUpdateKind {
     cpu: bool,
     memory: bool,
     gpu: bool,
+    // the metrics of the expanded GPU panel
+    gpu_details: bool,
     processes: bool,
+    filesystems: bool,
 }
 
 impl DataUpdateKind {
@@ -11,7 +14,9 @@ impl DataUpdateKind {
             cpu: true,
             memory: true,
             gpu: true,
+            gpu_details: true,
             processes: true,
+            filesystems: true,
         }
     }
 
@@ -20,7 +25,31 @@ impl DataUpdateKind {
             cpu: self.cpu,
             memory: self.memory,
             gpu: self.gpu,
+            gpu_details: self.gpu_details,
             processes: false,
+            filesystems: self.filesystems,
+        }
+    }
+
+    pub fn without_gpu_details(&self) -> DataUpdateKind {
+        Self {
+            cpu: self.cpu,
+            memory: self.memory,
+            gpu: self.gpu,
+            gpu_details: false,
+            processes: self.processes,
+            filesystems: self.filesystems,
+        }
+    }
+
+    pub fn without_filesystems(&self) -> DataUpdateKind {
+        Self {
+            cpu: self.cpu,
+            memory: self.memory,
+            gpu: self.gpu,
+            gpu_details: self.gpu_details,
+            processes: self.processes,
+            filesystems: false,
         }
     }
 
@@ -36,7 +65,15 @@ impl DataUpdateKind {
         self.gpu
     }
 
+    pub fn gpu_details(&self) -> bool {
+        self.gpu_details
+    }
+
     pub fn processes(&self) -> bool {
         self.processes
     }
+
+    pub fn filesystems(&self) -> bool {
+        self.filesystems
+    }
 }
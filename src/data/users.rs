@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use super::processes::Process;
+
+/// Resource usage of all the processes owned by a single user
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserSummary {
+    pub user: String,
+    pub processes: usize,
+    // percentage 0-100% per core, summed over all processes
+    pub cpu_usage: f32,
+    // bytes
+    pub memory: u64,
+    // bytes
+    pub gpu_memory: u64,
+}
+
+impl UserSummary {
+    fn new(user: &str) -> UserSummary {
+        UserSummary {
+            user: user.to_string(),
+            processes: 0,
+            cpu_usage: 0.0,
+            memory: 0,
+            gpu_memory: 0,
+        }
+    }
+}
+
+/// Totals the usage of `processes` per user, sorted by CPU usage (highest first).
+///
+/// Threads are skipped, since their memory is already accounted for by their process.
+pub fn summarize_by_user(processes: &[Process]) -> Vec<UserSummary> {
+    let mut summaries: HashMap<&str, UserSummary> = HashMap::new();
+    for p in processes.iter().filter(|p| !p.is_thread()) {
+        let summary = summaries
+            .entry(p.user.as_str())
+            .or_insert_with(|| UserSummary::new(&p.user));
+        summary.processes += 1;
+        summary.cpu_usage += p.cpu_usage;
+        summary.memory += p.memory;
+        summary.gpu_memory += p.gpu_memory;
+    }
+
+    let mut summaries: Vec<UserSummary> = summaries.into_values().collect();
+    summaries.sort_by(|a, b| {
+        b.cpu_usage
+            .partial_cmp(&a.cpu_usage)
+            .unwrap()
+            .then_with(|| a.user.cmp(&b.user))
+    });
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summarize_by_user;
+    use crate::data::processes::{Process, ProcessType};
+
+    fn process(user: &str, type_: ProcessType, cpu_usage: f32, memory: u64) -> Process {
+        Process {
+            pid: 0,
+            type_,
+            user: user.to_string(),
+            command: String::from("cmd"),
+            cpu_usage,
+            memory,
+            memory_usage: 0.0,
+            gpu_memory: memory / 2,
+            gpus: Vec::new(),
+            nice: 0,
+            affinity: vec![0],
+            last_cpu: None,
+            read_rate: 0,
+            write_rate: 0,
+            state: 'S',
+            start_time: 0,
+            threads: 1,
+            framework: None,
+            container: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_by_user() {
+        let processes = vec![
+            process("alice", ProcessType::Cpu, 10.0, 100),
+            process("bob", ProcessType::GpuCompute, 150.0, 1000),
+            process("alice", ProcessType::GpuCompute, 50.0, 300),
+            process("alice", ProcessType::UserThread, 50.0, 300),
+        ];
+        let summaries = summarize_by_user(&processes);
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].user, "bob");
+        assert_eq!(summaries[1].user, "alice");
+        assert_eq!(summaries[1].processes, 2);
+        assert_eq!(summaries[1].cpu_usage, 60.0);
+        assert_eq!(summaries[1].memory, 400);
+        assert_eq!(summaries[1].gpu_memory, 200);
+    }
+}
@@ -1,13 +1,30 @@
 use std::io;
+use std::process::exit;
 
+use mltop::cli::{Args, USAGE};
 use mltop::config::init_config;
-use mltop::tui::Tui;
+use mltop::tui::{restore_terminal, Tui};
 
 fn main() -> io::Result<()> {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("mltop: {}\n\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
     init_config();
     let mut app = Tui::new();
+    if let Some(rate) = args.refresh_rate {
+        app = app.with_refresh_rate(rate);
+    }
     let result = app.run();
-    ratatui::restore();
+    restore_terminal();
     app.render();
     result
 }
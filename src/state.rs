@@ -1,3 +1,6 @@
+use ratatui::layout::Rect;
+
+use crate::config::process_table::ProcessColumn;
 use crate::widgets::state::process_table::ProcessTableState;
 
 #[derive(Default)]
@@ -5,6 +8,8 @@ pub enum Mode {
     #[default]
     Normal,
     Filter,
+    Renice,
+    Affinity,
 }
 
 #[derive(Default)]
@@ -12,6 +17,24 @@ pub struct State {
     pub mode: Mode,
     pub filter_by: String,
     pub process_table: ProcessTableState,
+    pub show_user_summary: bool,
+    pub show_header: bool,
+    pub show_disks: bool,
+    pub show_network: bool,
+    pub show_filesystems: bool,
+    pub show_sensors: bool,
+    // text typed into the currently open popup
+    pub popup_input: String,
+    // pid the currently open popup acts on
+    pub popup_pid: Option<u32>,
+    pub gpu_expanded: bool,
+    pub memory_expanded: bool,
+    // where the action bar, the GPU panel and the CPUs were last rendered, for the mouse
+    pub action_bar_area: Rect,
+    pub gpu_area: Rect,
+    pub cpu_area: Rect,
+    // core of the CPU heatmap under the mouse
+    pub cpu_hovered: Option<usize>,
 }
 
 impl State {
@@ -20,6 +43,20 @@ impl State {
             mode: Mode::default(),
             filter_by: String::new(),
             process_table: ProcessTableState::default(),
+            show_user_summary: false,
+            show_header: true,
+            show_disks: false,
+            show_network: false,
+            show_filesystems: false,
+            show_sensors: false,
+            popup_input: String::new(),
+            popup_pid: None,
+            gpu_expanded: false,
+            memory_expanded: false,
+            action_bar_area: Rect::default(),
+            gpu_area: Rect::default(),
+            cpu_area: Rect::default(),
+            cpu_hovered: None,
         }
     }
 
@@ -47,11 +84,63 @@ impl State {
         self.process_table.selected_row()
     }
 
-    pub fn toggle_sort_by(&mut self) {
-        self.process_table.toggle_sort_by();
+    pub fn toggle_sort_by(&mut self, columns: &[ProcessColumn]) {
+        self.process_table.toggle_sort_by(columns);
     }
 
     pub fn toggle_show_threads(&mut self) {
         self.process_table.toggle_show_threads();
     }
+
+    pub fn toggle_show_io(&mut self) {
+        self.process_table.toggle_show_io();
+    }
+
+    pub fn toggle_user_summary(&mut self) {
+        self.show_user_summary = !self.show_user_summary;
+    }
+
+    pub fn toggle_show_header(&mut self) {
+        self.show_header = !self.show_header;
+    }
+
+    pub fn toggle_show_disks(&mut self) {
+        self.show_disks = !self.show_disks;
+    }
+
+    pub fn toggle_show_network(&mut self) {
+        self.show_network = !self.show_network;
+    }
+
+    pub fn toggle_show_filesystems(&mut self) {
+        self.show_filesystems = !self.show_filesystems;
+    }
+
+    pub fn toggle_show_sensors(&mut self) {
+        self.show_sensors = !self.show_sensors;
+    }
+
+    pub fn toggle_gpu_expanded(&mut self) {
+        self.gpu_expanded = !self.gpu_expanded;
+    }
+
+    pub fn toggle_memory_expanded(&mut self) {
+        self.memory_expanded = !self.memory_expanded;
+    }
+
+    pub fn sort_by(&mut self, column: ProcessColumn) {
+        self.process_table.sort_by = column;
+    }
+
+    pub fn open_popup(&mut self, mode: Mode, pid: u32, input: String) {
+        self.mode = mode;
+        self.popup_pid = Some(pid);
+        self.popup_input = input;
+    }
+
+    pub fn close_popup(&mut self) {
+        self.mode = Mode::Normal;
+        self.popup_pid = None;
+        self.popup_input.clear();
+    }
 }
@@ -1,35 +1,94 @@
 use crate::data::update_kind::DataUpdateKind;
 use nvml_wrapper::Nvml;
+use std::collections::HashSet;
+use std::fs::read_dir;
+use std::io;
+use std::path::Path;
 use sysinfo::ProcessesToUpdate;
 use sysinfo::{MemoryRefreshKind, Pid, ProcessRefreshKind};
 use sysinfo::{System as SysinfoSystem, UpdateKind};
 
-use crate::data::cpu::CpuSnapshot;
-use crate::data::gpu::GpuSnapshot;
+use crate::config::get_config;
+use crate::constants::SYSFS_ROOT;
+use crate::data::cgroup::CgroupOwnerCache;
+use crate::data::cgroup_limits::{CgroupLimits, CgroupTracker};
+use crate::data::clock::RateClock;
+use crate::data::cpu::{CpuSnapshot, CpuTimesTracker};
+use crate::data::disk::{DiskTracker, DisksSnapshot};
+use crate::data::filesystem::{FilesystemSampler, FilesystemsSnapshot};
+use crate::data::framework::FrameworkCache;
+use crate::data::gpu::{gpu_numa_nodes, GpuSnapshot};
+use crate::data::host::HostSnapshot;
 use crate::data::memory::MemorySnapshot;
+use crate::data::network::{NetworkSnapshot, NetworkTracker};
+use crate::data::numa::NumaSnapshot;
+use crate::data::power::{PowerMeter, PowerSnapshot};
 use crate::data::processes::ProcessesSnapshot;
+use crate::data::sensors::SensorsSnapshot;
 use crate::data::snapshot::DataSnapshot;
+use crate::data::topology::{online_cpus, CpuHwmons, CpuTopology};
 
 // SystemMonitor is a trait with Real and Fake implementations.
 // The fake implementation allows us to test different hardware configurations
 pub trait SystemMonitor {
     fn collect_snapshot(&mut self, kind: &DataUpdateKind) -> DataSnapshot;
+    /// Just the GPU, without touching the rates of the other metrics
+    fn collect_gpu(&mut self, with_details: bool) -> Option<GpuSnapshot>;
     fn kill_process(&self, pid: usize);
+    fn set_nice(&self, pid: usize, nice: i32) -> io::Result<()>;
+    fn set_affinity(&self, pid: usize, cpus: &[usize]) -> io::Result<()>;
+    /// The cpus an affinity can be set to
+    fn online_cpus(&self) -> Vec<usize>;
     fn gpu_available(&self) -> bool;
 }
 
 pub struct RealSystem {
     pub sys: SysinfoSystem,
     pub nvml: Option<Nvml>,
+    // the NUMA node of every GPU, by NVML index
+    pub gpu_nodes: Vec<Option<usize>>,
     pub components: sysinfo::Components,
+    pub users: sysinfo::Users,
+    // when the processes were last refreshed, to compute their disk rates
+    pub processes_clock: RateClock,
+    // pid and start time of the processes of the last refresh, to tell new ones apart
+    pub seen_processes: HashSet<(u32, u64)>,
+    pub frameworks: FrameworkCache,
+    pub containers: CgroupOwnerCache,
+    // maps logical cpus to physical cores, to find their temperatures
+    pub topology: CpuTopology,
+    pub cpu_hwmons: CpuHwmons,
+    pub cpu_times: CpuTimesTracker,
+    pub power: PowerMeter,
+    pub disks: DiskTracker,
+    pub network: NetworkTracker,
+    pub filesystems: FilesystemSampler,
+    pub cgroup: CgroupTracker,
+    pub host: HostSnapshot,
 }
 
 impl Default for RealSystem {
     fn default() -> RealSystem {
+        let nvml = Nvml::init().ok();
         RealSystem {
             sys: SysinfoSystem::new(),
-            nvml: Nvml::init().ok(),
+            gpu_nodes: nvml.as_ref().map(gpu_numa_nodes).unwrap_or_default(),
+            nvml,
             components: sysinfo::Components::new_with_refreshed_list(),
+            users: sysinfo::Users::new_with_refreshed_list(),
+            processes_clock: RateClock::default(),
+            seen_processes: HashSet::new(),
+            frameworks: FrameworkCache::default(),
+            containers: CgroupOwnerCache::default(),
+            topology: CpuTopology::read(),
+            cpu_hwmons: CpuHwmons::find(),
+            cpu_times: CpuTimesTracker::default(),
+            power: PowerMeter::new(Path::new(SYSFS_ROOT)),
+            disks: DiskTracker::default(),
+            network: NetworkTracker::new(Path::new(SYSFS_ROOT)),
+            filesystems: FilesystemSampler::default(),
+            cgroup: CgroupTracker::own(),
+            host: HostSnapshot::read(),
         }
     }
 }
@@ -37,22 +96,124 @@ impl Default for RealSystem {
 impl RealSystem {
     pub fn refresh(&mut self, kind: &DataUpdateKind) {
         self.sys.refresh_cpu_usage();
+        self.sys.refresh_cpu_frequency();
         self.sys
             .refresh_memory_specifics(MemoryRefreshKind::everything());
         self.components.refresh(true);
         if kind.processes() {
+            self.users.refresh();
             self.sys.refresh_processes_specifics(
                 ProcessesToUpdate::All,
                 true,
                 ProcessRefreshKind::default()
                     .with_cpu()
                     .with_memory()
-                    .with_cmd(UpdateKind::OnlyIfNotSet),
+                    .with_disk_usage()
+                    .with_cmd(UpdateKind::OnlyIfNotSet)
+                    .with_user(UpdateKind::OnlyIfNotSet),
             );
         };
     }
 }
 
+/// The ids of all threads of `pid`, or just `pid` if they can't be listed.
+fn thread_ids(pid: usize) -> Vec<usize> {
+    match read_dir(format!("/proc/{}/task", pid)) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(_) => vec![pid],
+    }
+}
+
+/// Runs `set` on every thread of `pid`, after reading its current value with `get`.
+/// If a thread fails, the threads changed so far are set back, so the process isn't
+/// left half changed. Threads that exit in the meantime are skipped.
+fn set_on_threads<T>(
+    pid: usize,
+    value: &T,
+    get: impl Fn(usize) -> io::Result<T>,
+    set: impl Fn(usize, &T) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut changed = Vec::new();
+    for tid in thread_ids(pid) {
+        let result = get(tid).and_then(|previous| set(tid, value).map(|()| previous));
+        match result {
+            Ok(previous) => changed.push((tid, previous)),
+            Err(e) if e.raw_os_error() == Some(libc::ESRCH) => {}
+            Err(e) => {
+                let stuck: Vec<String> = changed
+                    .into_iter()
+                    .filter(|(tid, previous)| set(*tid, previous).is_err())
+                    .map(|(tid, _)| tid.to_string())
+                    .collect();
+                return match stuck.is_empty() {
+                    true => Err(e),
+                    false => Err(io::Error::new(
+                        e.kind(),
+                        format!("{} (threads {} kept the change)", e, stuck.join(", ")),
+                    )),
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+fn get_thread_nice(tid: usize) -> io::Result<i32> {
+    // -1 is a valid niceness, so errors are told apart by errno
+    // SAFETY: errno is thread local and getpriority has no memory safety requirements
+    let nice = unsafe {
+        *libc::__errno_location() = 0;
+        libc::getpriority(libc::PRIO_PROCESS, tid as libc::id_t)
+    };
+    let error = io::Error::last_os_error();
+    match nice == -1 && error.raw_os_error() != Some(0) {
+        true => Err(error),
+        false => Ok(nice),
+    }
+}
+
+fn set_thread_nice(tid: usize, nice: &i32) -> io::Result<()> {
+    // SAFETY: setpriority has no memory safety requirements
+    match unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, *nice) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+fn get_thread_affinity(tid: usize) -> io::Result<libc::cpu_set_t> {
+    // SAFETY: cpu_set_t is a plain bitmask, so all zeroes is a valid (empty) set
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: `set` is a valid cpu_set_t and we pass its exact size
+    let result = unsafe {
+        libc::sched_getaffinity(
+            tid as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &mut set,
+        )
+    };
+    match result {
+        0 => Ok(set),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+fn set_thread_affinity(tid: usize, set: &libc::cpu_set_t) -> io::Result<()> {
+    // SAFETY: `set` is a valid cpu_set_t and we pass its exact size
+    let result = unsafe {
+        libc::sched_setaffinity(
+            tid as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            set,
+        )
+    };
+    match result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
 impl SystemMonitor for RealSystem {
     fn kill_process(&self, pid: usize) {
         if let Some(process) = self.sys.process(Pid::from(pid)) {
@@ -60,11 +221,42 @@ impl SystemMonitor for RealSystem {
         }
     }
 
+    fn set_nice(&self, pid: usize, nice: i32) -> io::Result<()> {
+        // on Linux the niceness is per thread too, so like the affinity we set it on every thread
+        set_on_threads(pid, &nice, get_thread_nice, set_thread_nice)
+    }
+
+    fn set_affinity(&self, pid: usize, cpus: &[usize]) -> io::Result<()> {
+        // SAFETY: cpu_set_t is a plain bitmask, so all zeroes is a valid (empty) set
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for cpu in cpus {
+            // SAFETY: CPU_SET panics instead of writing out of bounds
+            unsafe { libc::CPU_SET(*cpu, &mut set) };
+        }
+
+        // the affinity mask is per thread, so we apply it to every thread of the process
+        set_on_threads(pid, &set, get_thread_affinity, set_thread_affinity)
+    }
+
+    fn online_cpus(&self) -> Vec<usize> {
+        online_cpus(Path::new(SYSFS_ROOT)).unwrap_or_else(|| (0..self.sys.cpus().len()).collect())
+    }
+
     fn collect_snapshot(&mut self, kind: &DataUpdateKind) -> DataSnapshot {
         self.refresh(kind);
 
         let cpu = if kind.cpu() {
-            Some(CpuSnapshot::from_sysinfo(&self.sys, &self.components))
+            let temperatures = self
+                .cpu_hwmons
+                .read()
+                .per_cpu(&self.topology, self.sys.cpus().len());
+            let (breakdown, breakdowns) = self.cpu_times.measure();
+            Some(CpuSnapshot::from_sysinfo(
+                &self.sys,
+                &temperatures,
+                breakdown,
+                &breakdowns,
+            ))
         } else {
             None
         };
@@ -76,33 +268,61 @@ impl SystemMonitor for RealSystem {
         };
 
         let gpu = if kind.gpu() {
-            match &self.nvml {
-                Some(n) => {
-                    let result = GpuSnapshot::from_nvml(n);
-                    result.ok()
-                }
-                None => None,
-            }
+            self.collect_gpu(kind.gpu_details())
         } else {
             None
         };
 
         let processes = if kind.processes() {
-            Some(ProcessesSnapshot::from_sysinfo_nvml(
+            let elapsed = self.processes_clock.elapsed();
+            let mut snapshot = ProcessesSnapshot::from_sysinfo_nvml(
                 &self.sys,
+                &self.users,
                 self.nvml.as_ref(),
-            ))
+                elapsed,
+                &self.seen_processes,
+            );
+            self.seen_processes = snapshot
+                .processes
+                .iter()
+                .map(|p| (p.pid, p.start_time))
+                .collect();
+            self.frameworks.annotate(&mut snapshot.processes);
+            self.containers.annotate(&mut snapshot.processes);
+            Some(snapshot)
         } else {
             None
         };
+        let power = self
+            .power
+            .measure(gpu.as_ref().map(|g| g.power_usage as f32 / 1000.0));
+        self.host.refresh();
+
         DataSnapshot {
             cpu,
             memory,
             gpu,
             processes,
+            power,
+            disks: Some(self.disks.measure()),
+            network: Some(self.network.measure()),
+            filesystems: kind
+                .filesystems()
+                .then(|| self.filesystems.read(&get_config().filesystem.paths)),
+            host: Some(self.host.clone()),
+            numa: Some(NumaSnapshot {
+                gpu_nodes: self.gpu_nodes.clone(),
+                ..NumaSnapshot::read()
+            }),
+            sensors: Some(SensorsSnapshot::from_components(&self.components)),
+            cgroup: self.cgroup.measure(),
         }
     }
 
+    fn collect_gpu(&mut self, with_details: bool) -> Option<GpuSnapshot> {
+        GpuSnapshot::from_nvml(self.nvml.as_ref()?, with_details).ok()
+    }
+
     fn gpu_available(&self) -> bool {
         self.nvml.is_some()
     }
@@ -116,6 +336,14 @@ pub struct FakeSystem {
     memory: Option<MemorySnapshot>,
     gpu: Option<GpuSnapshot>,
     processes: Option<ProcessesSnapshot>,
+    power: Option<PowerSnapshot>,
+    disks: Option<DisksSnapshot>,
+    network: Option<NetworkSnapshot>,
+    filesystems: Option<FilesystemsSnapshot>,
+    host: Option<HostSnapshot>,
+    numa: Option<NumaSnapshot>,
+    sensors: Option<SensorsSnapshot>,
+    cgroup: Option<CgroupLimits>,
 }
 
 impl SystemMonitor for FakeSystem {
@@ -125,11 +353,36 @@ impl SystemMonitor for FakeSystem {
             memory: self.memory.clone(),
             gpu: self.gpu.clone(),
             processes: self.processes.clone(),
+            power: self.power.clone(),
+            disks: self.disks.clone(),
+            network: self.network.clone(),
+            filesystems: self.filesystems.clone(),
+            host: self.host.clone(),
+            numa: self.numa.clone(),
+            sensors: self.sensors.clone(),
+            cgroup: self.cgroup.clone(),
         }
     }
 
+    fn collect_gpu(&mut self, _: bool) -> Option<GpuSnapshot> {
+        self.gpu.clone()
+    }
+
     fn kill_process(&self, _: usize) {}
 
+    fn set_nice(&self, _: usize, _: i32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_affinity(&self, _: usize, _: &[usize]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn online_cpus(&self) -> Vec<usize> {
+        let cores = self.cpu.as_ref().map(|c| c.cores.len()).unwrap_or(0);
+        (0..cores).collect()
+    }
+
     fn gpu_available(&self) -> bool {
         self.gpu.is_some()
     }
@@ -153,4 +406,39 @@ impl FakeSystem {
         self.processes = Some(processes);
         self
     }
+    pub fn set_processes(&mut self, processes: ProcessesSnapshot) {
+        self.processes = Some(processes);
+    }
+    pub fn with_power(mut self, power: PowerSnapshot) -> Self {
+        self.power = Some(power);
+        self
+    }
+    pub fn with_disks(mut self, disks: DisksSnapshot) -> Self {
+        self.disks = Some(disks);
+        self
+    }
+    pub fn with_network(mut self, network: NetworkSnapshot) -> Self {
+        self.network = Some(network);
+        self
+    }
+    pub fn with_filesystems(mut self, filesystems: FilesystemsSnapshot) -> Self {
+        self.filesystems = Some(filesystems);
+        self
+    }
+    pub fn with_host(mut self, host: HostSnapshot) -> Self {
+        self.host = Some(host);
+        self
+    }
+    pub fn with_numa(mut self, numa: NumaSnapshot) -> Self {
+        self.numa = Some(numa);
+        self
+    }
+    pub fn with_sensors(mut self, sensors: SensorsSnapshot) -> Self {
+        self.sensors = Some(sensors);
+        self
+    }
+    pub fn with_cgroup(mut self, cgroup: CgroupLimits) -> Self {
+        self.cgroup = Some(cgroup);
+        self
+    }
 }
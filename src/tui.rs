@@ -1,34 +1,50 @@
+use std::collections::HashSet;
 use std::io::Stdout;
-use std::sync::mpsc::{self, Sender};
-use std::time::Duration;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
 use std::{io, thread};
 
 use crossterm::event::{
-    self, Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    self, DisableMouseCapture, EnableMouseCapture, Event as CrosstermEvent, KeyCode, KeyEvent,
+    KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
+use crossterm::execute;
 use ratatui::prelude::{Backend, CrosstermBackend};
 use ratatui::widgets::{StatefulWidget, Widget};
 use ratatui::{
     backend::TestBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Position},
     Terminal,
 };
 
-use crate::config::{get_config, Config, REFRESH_RATE_MILLIS};
-use crate::data::store::{DataStore, StoredSnapshot};
+use crate::config::process_table::ProcessColumn;
+use crate::config::{
+    get_config, Config, MIN_REFRESH_RATE_MILLIS, REFRESH_RATES_MILLIS, REFRESH_RATE_MILLIS,
+};
+use crate::data::filesystem::{FilesystemsSnapshot, LowSpaceWatch};
+use crate::data::processes::Process;
+use crate::data::store::{DataStore, LeakWatch, StoredSnapshot};
 use crate::data::system_data::SystemData;
 use crate::data::update_kind::DataUpdateKind;
 use crate::event::Event;
 use crate::message_bus::MessageBus;
 use crate::state::{Mode, State};
 use crate::system::{FakeSystem, RealSystem, SystemMonitor};
+use crate::utils::{format_bytes_short, format_cpu_list, format_uptime, parse_cpu_list};
 use crate::widgets::action_bar::ActionBarWidget;
 use crate::widgets::cpu::CpuWidget;
+use crate::widgets::disk::DiskWidget;
+use crate::widgets::filesystem::FilesystemWidget;
 use crate::widgets::gpu::{GpuWidget, GPU_WIDGET_HEIGHT};
+use crate::widgets::header::{HeaderWidget, HEADER_WIDGET_HEIGHT};
 use crate::widgets::line_graph::LineGraphWidget;
 use crate::widgets::memory::MemoryWidget;
-use crate::widgets::memory::MEMORY_WIDGET_HEIGHT;
+use crate::widgets::network::NetworkWidget;
+use crate::widgets::popup::InputPopupWidget;
+use crate::widgets::power::{PowerWidget, POWER_WIDGET_HEIGHT};
 use crate::widgets::process_table::ProcessTableWidget;
+use crate::widgets::sensors::SensorsWidget;
+use crate::widgets::user_summary::UserSummaryWidget;
 
 pub struct Tui<S: SystemMonitor, B: Backend> {
     config: &'static Config,
@@ -37,7 +53,11 @@ pub struct Tui<S: SystemMonitor, B: Backend> {
     data_store: DataStore,
     exit: bool,
     message_bus: MessageBus,
+    low_space: LowSpaceWatch,
+    leaks: LeakWatch,
     refresh_rate_ms: u64,
+    // tells the thread that triggers the refreshes about a new rate, once it runs
+    refresh_rate_tx: Option<Sender<u64>>,
     state: State,
     terminal: Terminal<B>,
 }
@@ -59,21 +79,56 @@ impl Default for Tui<RealSystem, CrosstermBackend<Stdout>> {
             message_bus.send("No GPU found.".to_string())
         }
 
+        let config = get_config();
         Tui {
-            config: get_config(),
+            config,
             system,
             data,
             data_store: DataStore::new(),
             exit: false,
             message_bus,
-            refresh_rate_ms: REFRESH_RATE_MILLIS,
+            low_space: LowSpaceWatch::default(),
+            leaks: LeakWatch::default(),
+            refresh_rate_ms: config.refresh_rate.max(MIN_REFRESH_RATE_MILLIS),
+            refresh_rate_tx: None,
             state: State::new(),
-            terminal: ratatui::init(),
+            terminal: init_terminal(),
         }
     }
 }
 
+/// Sets up the terminal like `ratatui::init`, with mouse capture on top.
+/// The panic hook undoes the mouse capture before ratatui's hook restores the rest.
+fn init_terminal() -> Terminal<CrosstermBackend<Stdout>> {
+    let terminal = ratatui::init();
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        hook(info);
+    }));
+    // the mouse is a nice to have, so the app still runs without it
+    let _ = execute!(io::stdout(), EnableMouseCapture);
+    terminal
+}
+
+/// Gives the terminal back in the state we found it, to be called once the app has stopped.
+pub fn restore_terminal() {
+    let _ = execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+}
+
 impl<S: SystemMonitor, B: Backend> Tui<S, B> {
+    /// Overrides the refresh rate of the config, in milliseconds
+    pub fn with_refresh_rate(mut self, refresh_rate_ms: u64) -> Self {
+        self.refresh_rate_ms = refresh_rate_ms.max(MIN_REFRESH_RATE_MILLIS);
+        self
+    }
+
+    /// The time between two refreshes, in milliseconds
+    pub fn refresh_rate_ms(&self) -> u64 {
+        self.refresh_rate_ms
+    }
+
     pub fn run(&mut self) -> io::Result<()> {
         // render -> update -> render to make the startup feel faster
         self.render();
@@ -83,7 +138,9 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
         let (tx, rx) = mpsc::channel();
 
         Self::spawn_crossterm_event_thread(tx.clone(), 300)?;
-        Self::spawn_render_event_thread(tx.clone(), self.refresh_rate_ms)?;
+        let (rate_tx, rate_rx) = mpsc::channel();
+        self.refresh_rate_tx = Some(rate_tx);
+        Self::spawn_render_event_thread(tx.clone(), rate_rx, self.refresh_rate_ms)?;
 
         while !self.exit {
             match rx.recv().unwrap() {
@@ -106,13 +163,24 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
         Ok(())
     }
 
-    /// Spawns a thread that sends an Event::Render to the mpsc channel
-    fn spawn_render_event_thread(tx: Sender<Event>, render_rate: u64) -> io::Result<()> {
-        let duration = Duration::from_millis(render_rate);
+    /// Spawns a thread that sends an Event::Render to the mpsc channel every
+    /// `render_rate` milliseconds. A new rate from `rate_rx` restarts the wait, so
+    /// going from 10s to 1s doesn't wait out the 10s.
+    fn spawn_render_event_thread(
+        tx: Sender<Event>,
+        rate_rx: Receiver<u64>,
+        render_rate: u64,
+    ) -> io::Result<()> {
         let custom_tx = tx.clone();
-        thread::spawn(move || loop {
-            thread::sleep(duration);
-            custom_tx.send(Event::Render).unwrap();
+        thread::spawn(move || {
+            let mut render_rate = render_rate;
+            loop {
+                match rate_rx.recv_timeout(Duration::from_millis(render_rate)) {
+                    Ok(rate) => render_rate = rate,
+                    Err(RecvTimeoutError::Timeout) => custom_tx.send(Event::Render).unwrap(),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
         });
         Ok(())
     }
@@ -123,24 +191,97 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
                 match self.state.mode {
                     Mode::Normal => self.handle_key_event_normal_mode(key_event),
                     Mode::Filter => self.handle_key_event_filter_mode(key_event),
+                    Mode::Renice | Mode::Affinity => self.handle_key_event_popup_mode(key_event),
                 }
             }
+            CrosstermEvent::Mouse(mouse_event) if matches!(self.state.mode, Mode::Normal) => {
+                self.handle_mouse_event(mouse_event)
+            }
             _ => {}
         };
         Ok(())
     }
 
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        let on_table =
+            !self.state.show_user_summary && self.state.process_table.area.contains(position);
+        match mouse_event.kind {
+            MouseEventKind::ScrollDown if on_table => self.move_down(),
+            MouseEventKind::ScrollUp if on_table => self.move_up(),
+            MouseEventKind::Moved => self.hover_cpu(position),
+            MouseEventKind::Down(MouseButton::Left) => {
+                if self.state.action_bar_area.contains(position) {
+                    let x = position.x - self.state.action_bar_area.x;
+                    if let Some(key) = ActionBarWidget::key_at(x) {
+                        self.handle_key_event_normal_mode(KeyEvent::from(key));
+                    }
+                } else if self.state.gpu_area.contains(position) {
+                    self.toggle_gpu_expanded();
+                } else if on_table {
+                    self.click_table(position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // shows the usage of the core under the mouse, when the CPUs are a heatmap
+    fn hover_cpu(&mut self, position: Position) {
+        let cpu = CpuWidget {
+            data: &self.data.cpu,
+            show_frequency: self.config.cpu.show_frequency,
+            numa: self.data.numa.as_ref(),
+            max_grid_height: self.config.cpu.max_grid_height,
+            hovered: self.state.cpu_hovered,
+            limits: self.data.cgroup.as_ref(),
+            theme: &self.config.theme,
+        };
+        let hovered = cpu.core_at(self.state.cpu_area, position);
+        if hovered != self.state.cpu_hovered {
+            self.state.cpu_hovered = hovered;
+            self.render();
+        }
+    }
+
+    // sorts by the clicked header, or selects the clicked row
+    fn click_table(&mut self, position: Position) {
+        if let Some(column) = self.state.process_table.column_at(position) {
+            self.sort_by(column);
+        } else if let Some(row) = self.state.process_table.row_at(position) {
+            self.state.select_row(row);
+            self.render();
+        }
+    }
+
     fn handle_key_event_normal_mode(&mut self, key_event: KeyEvent) {
         match key_event.modifiers {
             KeyModifiers::NONE => match key_event.code {
                 KeyCode::Char('q') => self.exit(),
                 KeyCode::Char('t') => self.toggle_threads(),
+                KeyCode::Char('u') => self.toggle_user_summary(),
+                KeyCode::Char('i') => self.toggle_io(),
+                KeyCode::Char('p') => self.toggle_pin(),
+                KeyCode::Char('c') => self.toggle_collapsed(),
+                KeyCode::Char('m') => self.toggle_memory_expanded(),
+                KeyCode::Char('d') => self.toggle_disks(),
+                KeyCode::Char('w') => self.toggle_network(),
+                KeyCode::Char('f') => self.toggle_filesystems(),
+                KeyCode::Char('s') => self.toggle_sensors(),
+                KeyCode::Char('h') => self.toggle_header(),
+                KeyCode::Char('+') => self.change_refresh_rate(true),
+                KeyCode::Char('-') => self.change_refresh_rate(false),
+                KeyCode::Char('n') => self.open_renice_popup(),
+                KeyCode::Char('a') => self.open_affinity_popup(),
                 KeyCode::Down | KeyCode::Char('j') => self.move_down(),
                 KeyCode::Up | KeyCode::Char('k') => self.move_up(),
+                KeyCode::F(3) => self.toggle_user_summary(),
                 KeyCode::F(4) | KeyCode::Char('/') => self.enter_filter_mode(),
                 KeyCode::Esc => self.deactivate(),
                 KeyCode::F(5) => self.toggle_threads(),
                 KeyCode::F(6) => self.toggle_sort_by(),
+                KeyCode::F(7) => self.open_renice_popup(),
+                KeyCode::F(8) => self.open_affinity_popup(),
                 KeyCode::F(9) => self.kill_process(),
                 _ => {}
             },
@@ -149,11 +290,12 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
                     self.exit()
                 }
             }
-            KeyModifiers::SHIFT => {
-                if let KeyCode::Char('G') = key_event.code {
-                    self.go_to_last()
-                }
-            }
+            KeyModifiers::SHIFT => match key_event.code {
+                KeyCode::Char('G') => self.go_to_last(),
+                // '+' needs shift on most keyboards
+                KeyCode::Char('+') => self.change_refresh_rate(true),
+                _ => {}
+            },
             _ => {}
         }
     }
@@ -178,6 +320,28 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
         self.render()
     }
 
+    fn handle_key_event_popup_mode(&mut self, key_event: KeyEvent) {
+        match key_event.modifiers {
+            KeyModifiers::NONE => match key_event.code {
+                KeyCode::Esc => self.state.close_popup(),
+                KeyCode::Enter => self.apply_popup(),
+                KeyCode::Char(c) => self.state.popup_input.push(c),
+                KeyCode::Backspace => {
+                    self.state.popup_input.pop();
+                }
+                _ => {}
+            },
+            // a nice of -5 needs shift for the minus on some keyboards
+            KeyModifiers::SHIFT => {
+                if let KeyCode::Char(c) = key_event.code {
+                    self.state.popup_input.push(c)
+                }
+            }
+            _ => {}
+        }
+        self.render()
+    }
+
     fn handle_render_event(&mut self) -> io::Result<()> {
         self.message_bus.check();
         self.update_data();
@@ -190,41 +354,116 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
             let theme = &self.config.theme;
 
             // -- build widgets --
+            let header = match self.state.show_header {
+                true => self.data.host.as_ref().map(|h| HeaderWidget { data: h }),
+                false => None,
+            };
             let cpu = CpuWidget {
                 data: &self.data.cpu,
+                show_frequency: self.config.cpu.show_frequency,
+                numa: self.data.numa.as_ref(),
+                max_grid_height: self.config.cpu.max_grid_height,
+                hovered: self.state.cpu_hovered,
+                limits: self.data.cgroup.as_ref(),
+                theme,
             };
             let memory = MemoryWidget {
                 data: &self.data.memory,
+                expanded: self.state.memory_expanded,
+                limits: self.data.cgroup.as_ref(),
+                theme,
             };
             let line_graph = LineGraphWidget {
                 data: &self.data_store,
                 max_gpu_mem: self.data.gpu.as_ref().map(|g| g.max_memory),
+                show_disk: self.config.graph.show_disk,
+                show_network: self.config.graph.show_network,
                 theme,
             };
-            let gpu = self.data.gpu.as_ref().map(|gd| GpuWidget { data: gd });
+            let power = self.data.power.as_ref().map(|p| PowerWidget { data: p });
+            let gpu = self.data.gpu.as_ref().map(|gd| GpuWidget {
+                data: gd,
+                expanded: self.state.gpu_expanded,
+            });
+            let disks = match self.state.show_disks {
+                true => self
+                    .data
+                    .disks
+                    .as_ref()
+                    .map(|d| DiskWidget { data: d, theme }),
+                false => None,
+            };
+            let network = match self.state.show_network {
+                true => self
+                    .data
+                    .network
+                    .as_ref()
+                    .map(|n| NetworkWidget { data: n, theme }),
+                false => None,
+            };
+            let filesystems = match self.state.show_filesystems {
+                true => self
+                    .data
+                    .filesystems
+                    .as_ref()
+                    .map(|f| FilesystemWidget { data: f, theme }),
+                false => None,
+            };
+            let sensors = match self.state.show_sensors {
+                true => self
+                    .data
+                    .sensors
+                    .as_ref()
+                    .map(|s| SensorsWidget { data: s, theme }),
+                false => None,
+            };
             let filter_by = match self.state.mode {
                 Mode::Filter => Some(self.state.filter_by.as_str()),
                 _ => None,
             };
             let process_table = ProcessTableWidget {
                 data: &self.data.processes,
+                history: &self.data_store.processes,
+                config: &self.config.process_table,
                 filter_by,
+                numa: self.data.gpu_numa(),
                 theme,
             };
             let action_bar = ActionBarWidget {
                 message: self.message_bus.read(),
                 filter_by,
+                refresh_rate_ms: self.refresh_rate_ms,
                 theme,
             };
 
             // -- build layout --
-            let mut constraints = vec![
-                Constraint::Length(cpu.grid_dimensions().0 + 1),
-                Constraint::Length(MEMORY_WIDGET_HEIGHT),
-                Constraint::Max(20),
-            ];
+            let mut constraints = vec![];
+            if header.is_some() {
+                constraints.push(Constraint::Length(HEADER_WIDGET_HEIGHT));
+            }
+            constraints.extend([
+                Constraint::Length(cpu.height(frame.area().width)),
+                Constraint::Length(memory.height()),
+            ]);
+            if power.is_some() {
+                constraints.push(Constraint::Length(POWER_WIDGET_HEIGHT));
+            }
+            constraints.push(Constraint::Max(20));
             if self.system.gpu_available() {
-                constraints.push(Constraint::Length(GPU_WIDGET_HEIGHT));
+                let height = gpu.as_ref().map_or(GPU_WIDGET_HEIGHT, |g| g.height());
+                constraints.push(Constraint::Length(height));
+            }
+            if let Some(d) = &disks {
+                constraints.push(Constraint::Length(d.height()));
+            }
+            if let Some(n) = &network {
+                constraints.push(Constraint::Length(n.height()));
+            }
+            if let Some(f) = &filesystems {
+                constraints.push(Constraint::Length(f.height()));
+            }
+            if let Some(s) = &sensors {
+                constraints.push(Constraint::Length(s.height()));
             }
             constraints.push(Constraint::Min(0));
             let areas = Layout::default()
@@ -240,15 +479,70 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
                 .split(*areas.last().unwrap());
 
             // -- render widgets --
+            // the areas are taken in the same order as the constraints were pushed
             let buf = frame.buffer_mut();
-            cpu.render(areas[0], buf);
-            memory.render(areas[1], buf);
-            line_graph.render(areas[2], buf);
-            if let Some(g) = gpu {
-                g.render(areas[3], buf);
+            let mut areas = areas.iter().copied();
+            if let Some(h) = header {
+                h.render(areas.next().unwrap(), buf);
+            }
+            let cpu_area = areas.next().unwrap();
+            cpu.render(cpu_area, buf);
+            self.state.cpu_area = cpu_area;
+            memory.render(areas.next().unwrap(), buf);
+            if let Some(p) = power {
+                p.render(areas.next().unwrap(), buf);
+            }
+            line_graph.render(areas.next().unwrap(), buf);
+            if self.system.gpu_available() {
+                let area = areas.next().unwrap();
+                if let Some(g) = gpu {
+                    g.render(area, buf);
+                    self.state.gpu_area = area;
+                }
+            }
+            if let Some(d) = disks {
+                d.render(areas.next().unwrap(), buf);
+            }
+            if let Some(n) = network {
+                n.render(areas.next().unwrap(), buf);
+            }
+            if let Some(f) = filesystems {
+                f.render(areas.next().unwrap(), buf);
+            }
+            if let Some(s) = sensors {
+                s.render(areas.next().unwrap(), buf);
+            }
+            if self.state.show_user_summary {
+                let user_summary = UserSummaryWidget {
+                    data: &self.data.processes,
+                    theme,
+                };
+                user_summary.render(remaining_areas[0], buf);
+            } else {
+                process_table.render(remaining_areas[0], buf, &mut self.state.process_table);
             }
-            process_table.render(remaining_areas[0], buf, &mut self.state.process_table);
             action_bar.render(remaining_areas[1], frame.buffer_mut());
+            self.state.action_bar_area = remaining_areas[1];
+
+            let popup = match self.state.mode {
+                Mode::Renice => Some(("Renice", "Nice value:", "-20 (highest) to 19 (lowest)")),
+                Mode::Affinity => Some(("CPU Affinity", "CPUs:", "e.g. 0-3,8")),
+                _ => None,
+            };
+            if let Some((title, prompt, hint)) = popup {
+                let title = match self.state.popup_pid {
+                    Some(pid) => format!("{} pid {}", title, pid),
+                    None => title.to_string(),
+                };
+                InputPopupWidget {
+                    title: &title,
+                    prompt,
+                    input: &self.state.popup_input,
+                    hint,
+                    theme,
+                }
+                .render(frame.area(), frame.buffer_mut());
+            }
         });
     }
 
@@ -288,52 +582,344 @@ impl<S: SystemMonitor, B: Backend> Tui<S, B> {
     }
 
     fn toggle_sort_by(&mut self) {
-        self.state.toggle_sort_by();
+        let columns = self.visible_columns();
+        self.state.toggle_sort_by(&columns);
         self.deactivate();
     }
 
+    fn sort_by(&mut self, column: ProcessColumn) {
+        self.state.sort_by(column);
+        self.deactivate();
+    }
+
+    fn visible_columns(&self) -> Vec<ProcessColumn> {
+        let table = ProcessTableWidget {
+            data: &self.data.processes,
+            history: &self.data_store.processes,
+            config: &self.config.process_table,
+            filter_by: None,
+            numa: self.data.gpu_numa(),
+            theme: &self.config.theme,
+        };
+        table
+            .visible_columns(&self.state.process_table)
+            .iter()
+            .map(|c| c.column())
+            .collect()
+    }
+
     fn toggle_threads(&mut self) {
         self.state.toggle_show_threads();
         self.deactivate();
     }
 
-    fn kill_process(&mut self) {
+    fn toggle_io(&mut self) {
+        self.state.toggle_show_io();
+        // don't keep sorting by a column that was just hidden
+        let columns = self.visible_columns();
+        if !columns.contains(&self.state.process_table.sort_by) {
+            self.state.toggle_sort_by(&columns);
+        }
+        self.deactivate();
+    }
+
+    fn toggle_pin(&mut self) {
+        if let Some(process) = self.selected_process() {
+            // pinning a thread pins the process it belongs to
+            let pid = process.owner.unwrap_or(process.pid);
+            let pinned_by_config = self
+                .data
+                .processes
+                .processes
+                .iter()
+                .any(|p| p.pid == pid && self.config.process_table.pins(&p.command));
+            // toggling it would change nothing on screen, as the pattern keeps it pinned
+            let message = match pinned_by_config {
+                true => format!("Pid {} is pinned by the config", pid),
+                false => match self.state.process_table.toggle_pin(pid) {
+                    true => format!("Pinned pid {}", pid),
+                    false => format!("Unpinned pid {}", pid),
+                },
+            };
+            self.message_bus.send(message);
+        }
+        self.deactivate();
+    }
+
+    // hides or shows the threads of the selected process, or of the process
+    // the selected thread belongs to
+    fn toggle_collapsed(&mut self) {
+        if let Some(process) = self.selected_process() {
+            let pid = process.owner.unwrap_or(process.pid);
+            self.state.process_table.toggle_collapsed(pid);
+        }
+        self.render();
+    }
+
+    fn toggle_memory_expanded(&mut self) {
+        self.state.toggle_memory_expanded();
+        self.render();
+    }
+
+    fn toggle_header(&mut self) {
+        self.state.toggle_show_header();
+        self.render();
+    }
+
+    /// Steps to the next slower (longer) or faster refresh rate
+    fn change_refresh_rate(&mut self, slower: bool) {
+        let current = self.refresh_rate_ms;
+        let next = match slower {
+            true => REFRESH_RATES_MILLIS.iter().find(|r| **r > current),
+            false => REFRESH_RATES_MILLIS.iter().rev().find(|r| **r < current),
+        };
+        if let Some(next) = next {
+            self.refresh_rate_ms = *next;
+            if let Some(tx) = &self.refresh_rate_tx {
+                let _ = tx.send(*next);
+            }
+        }
+        self.render();
+    }
+
+    fn toggle_disks(&mut self) {
+        self.state.toggle_show_disks();
+        self.render();
+    }
+
+    fn toggle_network(&mut self) {
+        self.state.toggle_show_network();
+        self.render();
+    }
+
+    fn toggle_filesystems(&mut self) {
+        self.state.toggle_show_filesystems();
+        self.render();
+    }
+
+    fn toggle_sensors(&mut self) {
+        self.state.toggle_show_sensors();
+        self.render();
+    }
+
+    fn toggle_gpu_expanded(&mut self) {
+        self.state.toggle_gpu_expanded();
+        // the details are only collected while expanded, so we fetch them right away,
+        // without a full refresh that would count as a sample in the history
+        if self.state.gpu_expanded {
+            if let Some(gpu) = self.system.collect_gpu(true) {
+                self.data.gpu = Some(gpu);
+            }
+        }
+        self.render();
+    }
+
+    fn toggle_user_summary(&mut self) {
+        self.state.toggle_user_summary();
+        self.deactivate();
+    }
+
+    // returns the process of the selected row of the table, if any
+    fn selected_process(&mut self) -> Option<Process> {
         // TODO: Potentially the State could get out of sync with what is
-        // reflected in the table, so this could kill the wrong PID.
+        // reflected in the table, so this could return the wrong process.
         // A more robust solution is needed
         let filter_by = match self.state.mode {
             Mode::Filter => Some(self.state.filter_by.as_str()),
             _ => None,
         };
-        if let Some(selected_row) = self.state.selected_row() {
-            // TODO: Shouldn't have to build the full widget to do this op, should decouple
-            // the process ordering/filtering from displaying.
-            let table = ProcessTableWidget {
-                data: &self.data.processes,
-                filter_by,
-                theme: &self.config.theme,
-            };
-            if let Some(pid) = table.get_nth_pid(selected_row, &mut self.state.process_table) {
-                self.system.kill_process(pid as usize);
-                self.message_bus.send(format!("Killed pid {}", pid));
-            }
+        let selected_row = self.state.selected_row()?;
+        // TODO: Shouldn't have to build the full widget to do this op, should decouple
+        // the process ordering/filtering from displaying.
+        let table = ProcessTableWidget {
+            data: &self.data.processes,
+            history: &self.data_store.processes,
+            config: &self.config.process_table,
+            filter_by,
+            numa: self.data.gpu_numa(),
+            theme: &self.config.theme,
+        };
+        table
+            .get_processes(&mut self.state.process_table)
+            .into_iter()
+            .nth(selected_row)
+    }
+
+    fn kill_process(&mut self) {
+        if let Some(process) = self.selected_process() {
+            self.system.kill_process(process.pid as usize);
+            self.message_bus.send(format!("Killed pid {}", process.pid));
         }
         self.deactivate();
     }
 
+    fn open_renice_popup(&mut self) {
+        if let Some(process) = self.selected_process() {
+            self.state
+                .open_popup(Mode::Renice, process.pid, process.nice.to_string());
+        }
+        self.render();
+    }
+
+    fn open_affinity_popup(&mut self) {
+        if let Some(process) = self.selected_process() {
+            self.state.open_popup(
+                Mode::Affinity,
+                process.pid,
+                format_cpu_list(&process.affinity),
+            );
+        }
+        self.render();
+    }
+
+    fn apply_popup(&mut self) {
+        let Some(pid) = self.state.popup_pid else {
+            return self.state.close_popup();
+        };
+        let input = self.state.popup_input.trim();
+        let message = match self.state.mode {
+            Mode::Renice => match input.parse::<i32>() {
+                Ok(nice) if (-20..=19).contains(&nice) => {
+                    match self.system.set_nice(pid as usize, nice) {
+                        Ok(()) => format!("Set nice of pid {} to {}", pid, nice),
+                        Err(e) => format!("Failed to renice pid {}: {}", pid, e),
+                    }
+                }
+                _ => format!("Invalid nice value: {}", input),
+            },
+            Mode::Affinity => {
+                let online = self.system.online_cpus();
+                match parse_cpu_list(input) {
+                    Some(cpus) if !cpus.is_empty() && cpus.iter().all(|c| online.contains(c)) => {
+                        match self.system.set_affinity(pid as usize, &cpus) {
+                            Ok(()) => {
+                                format!("Set affinity of pid {} to {}", pid, format_cpu_list(&cpus))
+                            }
+                            Err(e) => format!("Failed to set affinity of pid {}: {}", pid, e),
+                        }
+                    }
+                    _ => format!("Invalid CPU list: {}", input),
+                }
+            }
+            _ => return self.state.close_popup(),
+        };
+        self.message_bus.send(message);
+        self.state.close_popup();
+        self.state.deactivate_table();
+    }
+
     fn update_data(&mut self) {
+        self.update_data_at(Instant::now());
+    }
+
+    fn update_data_at(&mut self, now: Instant) {
         // we don't update processes if the table is active, because
         // then it gets annoying to select the right row if the table
         // is refreshing while we move
-        let update_kind = match self.state.table_is_active() {
+        let mut update_kind = match self.state.table_is_active() {
             true => DataUpdateKind::all().without_processes(),
             false => DataUpdateKind::all(),
         };
+        if !self.state.gpu_expanded {
+            update_kind = update_kind.without_gpu_details();
+        }
+        // the filesystems are only needed for their panel and the low space warnings
+        let warnings_due =
+            self.config.filesystem.warning_threshold > 0.0 && self.low_space.due(now);
+        if !self.state.show_filesystems && !warnings_due {
+            update_kind = update_kind.without_filesystems();
+        }
 
         let data_snapshot = self.system.collect_snapshot(&update_kind);
-        let stored = StoredSnapshot::from_data_snapshot(&data_snapshot);
+        let stored = StoredSnapshot::from_data_snapshot(&data_snapshot, now);
         self.data_store.save(stored);
+        if let Some(f) = &data_snapshot.filesystems {
+            self.warn_low_space(f, now);
+        }
+        if let Some(p) = &data_snapshot.processes {
+            self.data_store
+                .save_processes(p, &self.config.memory_leak, now);
+            let alive: HashSet<u32> = p.processes.iter().map(|p| p.pid).collect();
+            self.state.process_table.retain_alive(&alive);
+        }
         self.data.update_from_snapshot(data_snapshot);
+        self.warn_memory_leaks();
+    }
+
+    fn warn_memory_leaks(&mut self) {
+        let started = self.leaks.check(&self.data_store.processes);
+        if started.is_empty() {
+            return;
+        }
+        let leaks: Vec<String> = started
+            .iter()
+            .filter_map(|pid| {
+                let process = self
+                    .data
+                    .processes
+                    .processes
+                    .iter()
+                    .find(|p| p.pid == *pid)?;
+                let growth = self.data_store.processes.get(pid)?.memory_growth?;
+                let name = process.command.split_whitespace().next().unwrap_or("");
+                Some(format!(
+                    "{} ({}) +{}/h",
+                    name.rsplit('/').next().unwrap_or(name),
+                    pid,
+                    format_bytes_short((growth * 3600.0) as u64)
+                ))
+            })
+            .collect();
+        if leaks.is_empty() {
+            return;
+        }
+        // every leaking process together eats into the available memory
+        let growth: f64 = self
+            .data_store
+            .processes
+            .values()
+            .filter_map(|h| h.memory_growth)
+            .sum();
+        // inside a container or job, we run out when we reach its limit
+        let mut available = self.data.memory.details.available;
+        if let Some(c) = &self.data.cgroup {
+            if let Some(max) = c.memory_max {
+                available = available.min(max.saturating_sub(c.memory_used));
+            }
+        }
+        let message = format!("Memory keeps growing: {}", leaks.join(", "));
+        self.message_bus.send(match growth > 0.0 {
+            true => format!(
+                "{}, memory runs out in ~{}",
+                message,
+                format_uptime((available as f64 / growth) as u64)
+            ),
+            false => message,
+        });
+    }
+
+    fn warn_low_space(&mut self, filesystems: &FilesystemsSnapshot, now: Instant) {
+        let threshold = self.config.filesystem.warning_threshold;
+        if threshold <= 0.0 {
+            return;
+        }
+        let warnings: Vec<String> = self
+            .low_space
+            .check(filesystems, threshold, now)
+            .iter()
+            .map(|f| {
+                format!(
+                    "{} is {:.0}% full ({} free)",
+                    f.path,
+                    f.percent(),
+                    format_bytes_short(f.free())
+                )
+            })
+            .collect();
+        if !warnings.is_empty() {
+            self.message_bus
+                .send(format!("Low disk space: {}", warnings.join(", ")));
+        }
     }
 }
 
@@ -355,9 +941,43 @@ impl Tui<FakeSystem, TestBackend> {
             data_store: DataStore::new(),
             exit: false,
             message_bus,
+            low_space: LowSpaceWatch::default(),
+            leaks: LeakWatch::default(),
             refresh_rate_ms: REFRESH_RATE_MILLIS,
+            refresh_rate_tx: None,
             state: State::new(),
             terminal: Terminal::new(backend).unwrap(),
         }
     }
+
+    /// Handles a press of `key` like the event loop does, then renders
+    pub fn press(&mut self, key: KeyCode) {
+        self.press_with(key, KeyModifiers::NONE);
+    }
+
+    pub fn press_with(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let _ = self.handle_crossterm_event(CrosstermEvent::Key(KeyEvent::new(key, modifiers)));
+        self.render();
+    }
+
+    /// Collects the data like a refresh at `at` would
+    pub fn update_at(&mut self, at: Instant) {
+        self.update_data_at(at);
+    }
+
+    pub fn system_mut(&mut self) -> &mut FakeSystem {
+        &mut self.system
+    }
+
+    /// The text of the last rendered frame, a line per row
+    pub fn rendered_text(&self) -> String {
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
@@ -9,9 +9,101 @@ pub fn fast_int_sqrt(x: usize) -> usize {
     result
 }
 
+/// Parses a Linux cpu list such as "0-3,8,10-11" into the list of cpu ids
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().ok()?;
+                let end: usize = end.trim().parse().ok()?;
+                if start > end {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.trim().parse().ok()?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Some(cpus)
+}
+
+/// Formats a list of cpu ids in the compact Linux cpu list format, e.g. "0-3,8"
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < cpus.len() {
+        let start = cpus[i];
+        while i + 1 < cpus.len() && cpus[i + 1] == cpus[i] + 1 {
+            i += 1;
+        }
+        match cpus[i] == start {
+            true => ranges.push(start.to_string()),
+            false => ranges.push(format!("{}-{}", start, cpus[i])),
+        }
+        i += 1;
+    }
+    ranges.join(",")
+}
+
+/// Formats a byte count with a binary unit suffix, using at most 5 characters, e.g. "12.3M"
+pub fn format_bytes_short(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit == 0 || value >= 100.0 {
+        true => format!("{:.0}{}", value, UNITS[unit]),
+        false => format!("{:.1}{}", value, UNITS[unit]),
+    }
+}
+
+/// Formats a start time (seconds since epoch) in local time, as "HH:MM" if it was in
+/// the last 24 hours or as the date ("Oct19") otherwise
+pub fn format_start_time(start: u64) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let time = start as libc::time_t;
+    // SAFETY: tm is a plain C struct, so all zeroes is valid, and localtime_r only
+    // writes to the struct we pass it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return String::from("?");
+    }
+
+    match now.saturating_sub(start) < 24 * 60 * 60 {
+        true => format!("{:02}:{:02}", tm.tm_hour, tm.tm_min),
+        false => format!("{}{:02}", MONTHS[tm.tm_mon as usize % 12], tm.tm_mday),
+    }
+}
+
+/// Formats a duration in seconds with its two largest units, e.g. "3d 4h" or "5h 12m"
+pub fn format_uptime(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::fast_int_sqrt;
+    use super::{
+        fast_int_sqrt, format_bytes_short, format_cpu_list, format_uptime, parse_cpu_list,
+    };
 
     #[test]
     fn test_fast_int_sqrt() {
@@ -19,4 +111,36 @@ mod tests {
         assert_eq!(fast_int_sqrt(5), 2);
         assert_eq!(fast_int_sqrt(32), 5);
     }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8"), Some(vec![0, 1, 2, 3, 8]));
+        assert_eq!(parse_cpu_list("5\n"), Some(vec![5]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a"), None);
+    }
+
+    #[test]
+    fn test_format_cpu_list() {
+        assert_eq!(format_cpu_list(&[0, 1, 2, 3, 8, 10, 11]), "0-3,8,10-11");
+        assert_eq!(format_cpu_list(&[4]), "4");
+        assert_eq!(format_cpu_list(&[]), "");
+    }
+
+    #[test]
+    fn test_format_bytes_short() {
+        assert_eq!(format_bytes_short(0), "0B");
+        assert_eq!(format_bytes_short(1000), "1000B");
+        assert_eq!(format_bytes_short(1536), "1.5K");
+        assert_eq!(format_bytes_short(300 * 1024 * 1024), "300M");
+        assert_eq!(format_bytes_short(5 * 1024_u64.pow(4)), "5.0T");
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(59), "0m");
+        assert_eq!(format_uptime(5 * 3600 + 12 * 60), "5h 12m");
+        assert_eq!(format_uptime(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+    }
 }
@@ -1,4 +1,5 @@
 use crate::config::Theme;
+use crossterm::event::KeyCode;
 use ratatui::widgets::Widget;
 use ratatui::{
     buffer::Buffer,
@@ -8,19 +9,48 @@ use ratatui::{
     widgets::{Block, Paragraph},
 };
 
-const FOOTER: [(&str, &str); 4] = [
-    ("F4", "Filter"),
-    ("F5", "Threads"),
-    ("F6", "SortBy"),
-    ("F9", "Kill"),
+// (function key number, action)
+const FOOTER: [(u8, &str); 7] = [
+    (3, "Users"),
+    (4, "Filter"),
+    (5, "Threads"),
+    (6, "SortBy"),
+    (7, "Nice"),
+    (8, "Affinity"),
+    (9, "Kill"),
 ];
 
 pub struct ActionBarWidget<'a> {
     pub message: Option<&'a str>,
     pub filter_by: Option<&'a str>,
+    pub refresh_rate_ms: u64,
     pub theme: &'a Theme,
 }
 
+/// e.g. "500ms", "2s" or "1.5s"
+fn format_refresh_rate(ms: u64) -> String {
+    match ms {
+        ms if ms < 1000 => format!("{}ms", ms),
+        ms if ms % 1000 == 0 => format!("{}s", ms / 1000),
+        ms => format!("{:.1}s", ms as f32 / 1000.0),
+    }
+}
+
+impl<'a> ActionBarWidget<'a> {
+    /// The key of the entry `x` columns from the left of the action bar, if any
+    pub fn key_at(x: u16) -> Option<KeyCode> {
+        let mut start = 0;
+        for (key, action) in FOOTER {
+            let end = start + format!(" F{}", key).len() + action.len();
+            if (start..end).contains(&(x as usize)) {
+                return Some(KeyCode::F(key));
+            }
+            start = end;
+        }
+        None
+    }
+}
+
 impl<'a> Widget for ActionBarWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let key_style: Style = Style::new()
@@ -34,7 +64,7 @@ impl<'a> Widget for ActionBarWidget<'a> {
             .iter()
             .flat_map(|f| {
                 vec![
-                    Span::styled(format!(" {}", f.0), key_style),
+                    Span::styled(format!(" F{}", f.0), key_style),
                     Span::styled(f.1, cmd_style),
                 ]
             })
@@ -43,6 +73,10 @@ impl<'a> Widget for ActionBarWidget<'a> {
         if let Some(s) = self.filter_by {
             spans.push(Span::raw(format!(" Filter: {} ", s)));
         };
+        spans.push(Span::raw(format!(
+            " Refresh: {} ",
+            format_refresh_rate(self.refresh_rate_ms)
+        )));
 
         let used_width: usize = spans.iter().map(|s| s.content.len()).sum();
         let message_width: usize = match self.message {
@@ -69,3 +103,25 @@ impl<'a> Widget for ActionBarWidget<'a> {
             .render(area, buf);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{format_refresh_rate, ActionBarWidget};
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn test_key_at() {
+        // " F3Users F4Filter..."
+        assert_eq!(ActionBarWidget::key_at(0), Some(KeyCode::F(3)));
+        assert_eq!(ActionBarWidget::key_at(7), Some(KeyCode::F(3)));
+        assert_eq!(ActionBarWidget::key_at(8), Some(KeyCode::F(4)));
+        assert_eq!(ActionBarWidget::key_at(200), None);
+    }
+
+    #[test]
+    fn test_format_refresh_rate() {
+        assert_eq!(format_refresh_rate(250), "250ms");
+        assert_eq!(format_refresh_rate(2000), "2s");
+        assert_eq!(format_refresh_rate(1500), "1.5s");
+    }
+}
@@ -1,62 +1,270 @@
-use crate::data::cpu::CpuSnapshot;
-use crate::utils::fast_int_sqrt;
-use crate::widgets::percentage_bar::percentage_bar;
+use crate::config::Theme;
+use crate::data::cgroup_limits::CgroupLimits;
+use crate::data::cpu::{CpuBreakdown, CpuSnapshot};
+use crate::data::numa::{NumaNode, NumaSnapshot};
+use crate::utils::{fast_int_sqrt, format_bytes_short};
+use crate::widgets::percentage_bar::{percentage_bar, stacked_bar, usage_color};
 
 use ratatui::style::{Color, Style};
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
+    layout::{Position, Rect},
     text::{Line, Span, Text},
     widgets::{Paragraph, Widget},
 };
 
+// width of the frequency next to each core's bar, e.g. " 3.4G"
+const FREQUENCY_WIDTH: u16 = 5;
+
+// each core of the heatmap is a colored cell followed by a space
+const HEATMAP_CELL: &str = "██";
+const HEATMAP_CELL_WIDTH: u16 = 3;
+const HEATMAP_INDENT: &str = "    ";
+// cores outside of our cgroup's cpuset
+const HEATMAP_DISALLOWED_CELL: &str = "░░";
+
 pub struct CpuWidget<'a> {
     pub data: &'a CpuSnapshot,
+    pub show_frequency: bool,
+    // groups the cores by NUMA node, if there's more than one
+    pub numa: Option<&'a NumaSnapshot>,
+    // the grid of bars turns into a heatmap when it would be taller than this
+    pub max_grid_height: u16,
+    // core under the mouse, whose usage is shown under the heatmap
+    pub hovered: Option<usize>,
+    // limits of the container or job mltop runs in
+    pub limits: Option<&'a CgroupLimits>,
+    pub theme: &'a Theme,
+}
+
+// a line of the heatmap: the line of a NUMA node, or a row of cores
+enum HeatmapLine<'a> {
+    Node(&'a NumaNode),
+    Cores(Vec<usize>),
+}
+
+fn format_ghz(mhz: u64) -> String {
+    format!("{:.1}", mhz as f32 / 1000.0)
+}
+
+// returns the dimensions of a grid to fit `cores` cpu cores
+// in a pseudo-rectangular way (Rows, Cols)
+fn grid_dimensions_of(cores: usize) -> (u16, u16) {
+    if cores <= 3 {
+        return (cores as u16, 1);
+    }
+    let cpu_rows = fast_int_sqrt(cores) as u16;
+    let mut cpu_cols: u16 = 0;
+    while ((cpu_cols * cpu_rows) as usize) < cores {
+        cpu_cols += 1;
+    }
+    (cpu_rows, cpu_cols)
 }
 
 impl<'a> CpuWidget<'a> {
     // returns the dimensions of a grid to fit all cpu cores
     // in a pseudo-rectangular way (Rows, Cols)
     pub fn grid_dimensions(&self) -> (u16, u16) {
-        let cores = self.data.cores.len();
-        if cores <= 3 {
-            return (cores as u16, 1);
-        }
-        let cpu_rows = fast_int_sqrt(cores) as u16;
-        let mut cpu_cols: u16 = 0;
-        while ((cpu_cols * cpu_rows) as usize) < cores {
-            cpu_cols += 1;
-        }
-        (cpu_rows, cpu_cols)
+        grid_dimensions_of(self.data.cores.len())
     }
 
     pub fn grid_height(&self) -> u16 {
         self.grid_dimensions().0
     }
-}
 
-impl<'a> Widget for CpuWidget<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let (cpu_rows, cpu_cols) = self.grid_dimensions();
+    /// The total line, and a grid per NUMA node with a line for the node. With the
+    /// heatmap, its rows and the readout of the hovered core instead of the grids.
+    pub fn height(&self, width: u16) -> u16 {
+        match self.is_heatmap() {
+            true => self.heatmap_lines(width).len() as u16 + 2,
+            false => self.grids_height() + 1,
+        }
+    }
 
-        let mut spans = vec![Span::styled("  Total ", Style::default().fg(Color::Cyan))];
+    fn grids_height(&self) -> u16 {
+        self.groups()
+            .iter()
+            .map(|(node, cpus)| grid_dimensions_of(cpus.len()).0 + node.is_some() as u16)
+            .sum()
+    }
 
-        let usage = self.data.usage;
-        let text = format!("{:.1}%", usage);
-        let total_bar = percentage_bar(area.width - 16, usage, &text);
-        spans.extend(total_bar);
+    pub fn is_heatmap(&self) -> bool {
+        self.grids_height() > self.max_grid_height
+    }
 
-        let cores_len = self.data.cores.len();
+    fn heatmap_lines(&self, width: u16) -> Vec<HeatmapLine<'a>> {
+        let indent = HEATMAP_INDENT.len() as u16;
+        let per_row = (width.saturating_sub(indent) / HEATMAP_CELL_WIDTH).max(1) as usize;
+        let mut lines = Vec::new();
+        for (node, cpus) in self.groups() {
+            if let Some(node) = node {
+                lines.push(HeatmapLine::Node(node));
+            }
+            lines.extend(cpus.chunks(per_row).map(|c| HeatmapLine::Cores(c.to_vec())));
+        }
+        lines
+    }
 
-        let mut lines = vec![Line::from(spans).left_aligned()];
+    /// The core of the heatmap rendered at `position`, if the widget was rendered in `area`
+    pub fn core_at(&self, area: Rect, position: Position) -> Option<usize> {
+        if !self.is_heatmap() || !area.contains(position) {
+            return None;
+        }
+        // the first line is the total
+        let line = (position.y - area.y).checked_sub(1)?;
+        let HeatmapLine::Cores(cpus) = self
+            .heatmap_lines(area.width)
+            .into_iter()
+            .nth(line as usize)?
+        else {
+            return None;
+        };
+        let x = (position.x - area.x).checked_sub(HEATMAP_INDENT.len() as u16)?;
+        // the space after a cell doesn't belong to any core
+        if x % HEATMAP_CELL_WIDTH >= HEATMAP_CELL_WIDTH - 1 {
+            return None;
+        }
+        cpus.get((x / HEATMAP_CELL_WIDTH) as usize).copied()
+    }
+
+    fn heatmap_row(&self, cpus: &[usize]) -> Line<'static> {
+        let mut spans = vec![Span::raw(HEATMAP_INDENT)];
+        for &i in cpus {
+            let usage = self.data.cores[i].usage;
+            let color = match usage {
+                _ if self.hovered == Some(i) => Color::White,
+                u if u < 1.0 => self.theme.bar_text,
+                u => usage_color(u),
+            };
+            let cell = match self.allows_cpu(i) {
+                true => HEATMAP_CELL,
+                false => HEATMAP_DISALLOWED_CELL,
+            };
+            spans.push(Span::styled(cell, Style::default().fg(color)));
+            spans.push(Span::raw(" "));
+        }
+        Line::from(spans)
+    }
+
+    fn readout_line(&self) -> Line<'static> {
+        let label = |s: String| Span::styled(s, Style::default().fg(Color::Cyan));
+        let Some((i, core)) = self
+            .hovered
+            .and_then(|i| self.data.cores.get(i).map(|c| (i, c)))
+        else {
+            return Line::from(Span::styled(
+                format!("{}Hover over a core to see its usage", HEATMAP_INDENT),
+                Style::default().fg(Color::DarkGray),
+            ));
+        };
+        let mut spans = vec![
+            label(format!("{}CPU {}", HEATMAP_INDENT, i)),
+            Span::raw(format!(
+                " {:.1}% {:.0}°C {} GHz",
+                core.usage,
+                core.temp,
+                format_ghz(core.frequency)
+            )),
+        ];
+        let b = &core.breakdown;
+        if b.is_known() {
+            for (name, value) in [
+                ("usr", b.user + b.nice),
+                ("sys", b.system),
+                ("io", b.iowait),
+                ("irq", b.irq + b.softirq),
+                ("st", b.steal),
+            ] {
+                spans.push(label(format!("  {}", name)));
+                spans.push(Span::raw(format!(" {:.0}%", value)));
+            }
+        }
+        Line::from(spans)
+    }
+
+    fn allows_cpu(&self, cpu: usize) -> bool {
+        self.limits.is_none_or(|l| l.allows_cpu(cpu))
+    }
+
+    // e.g. " Limit 3.2/6 CPUs", when our cgroup can't use every cpu
+    fn limit_text(&self) -> Option<String> {
+        let limits = self.limits?;
+        let limit = limits.cpu_limit(self.data.cores.len())?;
+        let limit = match limit.fract() == 0.0 {
+            true => format!("{:.0}", limit),
+            false => format!("{:.1}", limit),
+        };
+        Some(match limits.cpu_usage {
+            Some(usage) => format!(" Limit {:.1}/{} CPUs", usage, limit),
+            None => format!(" Limit {} CPUs", limit),
+        })
+    }
 
-        let total_width = area.width.saturating_sub(10);
-        let core_width: u16 = if cpu_cols > 0 {
-            total_width / cpu_cols
-        } else {
-            0
+    // the cpus of each NUMA node, or all the cpus without NUMA
+    fn groups(&self) -> Vec<(Option<&'a NumaNode>, Vec<usize>)> {
+        let cores = self.data.cores.len();
+        match self.numa.filter(|n| n.is_numa()) {
+            Some(numa) => numa
+                .nodes
+                .iter()
+                .map(|n| {
+                    let cpus = n.cpus.iter().copied().filter(|c| *c < cores).collect();
+                    (Some(n), cpus)
+                })
+                .collect(),
+            None => vec![(None, (0..cores).collect())],
+        }
+    }
+
+    // the usage split by cpu state like htop, once we have the breakdown
+    fn usage_bar(
+        &self,
+        width: u16,
+        usage: f32,
+        breakdown: &CpuBreakdown,
+        text: &str,
+    ) -> Vec<Span<'static>> {
+        if !breakdown.is_known() {
+            return percentage_bar(width, usage, text);
+        }
+        let t = self.theme;
+        let segments = [
+            (breakdown.nice, t.cpu_nice),
+            (breakdown.user, t.cpu_user),
+            (breakdown.system, t.cpu_system),
+            (breakdown.irq, t.cpu_irq),
+            (breakdown.softirq, t.cpu_softirq),
+            (breakdown.steal, t.cpu_steal),
+            (breakdown.iowait, t.cpu_iowait),
+        ];
+        stacked_bar(width, &segments, text)
+    }
+
+    fn node_line(node: &NumaNode, width: u16) -> Line<'static> {
+        let mut spans = vec![Span::styled(
+            format!("  Node {:<2} ", node.id),
+            Style::default().fg(Color::Cyan),
+        )];
+        let text = format!(
+            "Mem {}/{}",
+            format_bytes_short(node.memory_used),
+            format_bytes_short(node.memory_total)
+        );
+        let percentage = match node.memory_total {
+            0 => 0.0,
+            total => node.memory_used as f32 / total as f32 * 100.0,
         };
+        // width: label (11) + brackets (2), aligned with the total bar
+        spans.extend(percentage_bar(width.saturating_sub(19), percentage, &text));
+        Line::from(spans)
+    }
+
+    fn grid_lines(&self, cpus: &[usize], width: u16) -> Vec<Line<'static>> {
+        let (cpu_rows, cpu_cols) = grid_dimensions_of(cpus.len());
+        let total_width = width.saturating_sub(10);
+        let core_width: u16 = total_width.checked_div(cpu_cols).unwrap_or(0);
 
+        let mut lines = Vec::new();
         for r in 0..cpu_rows {
             let mut spans = vec![Span::raw("    ")];
 
@@ -69,16 +277,18 @@ impl<'a> Widget for CpuWidget<'a> {
             }
 
             'inner: for c in 0..cpu_cols {
-                let i = (c * cpu_rows + r) as usize;
-
-                if i >= cores_len {
+                let Some(&i) = cpus.get((c * cpu_rows + r) as usize) else {
                     break 'inner;
-                }
+                };
 
-                // cpu number
+                // cpu number, grayed out if our cgroup can't run on it
+                let color = match self.allows_cpu(i) {
+                    true => Color::Cyan,
+                    false => Color::DarkGray,
+                };
                 spans.push(Span::styled(
                     format!("  {:>2}", i),
-                    Style::default().fg(Color::Cyan),
+                    Style::default().fg(color),
                 ));
 
                 // bar
@@ -87,11 +297,20 @@ impl<'a> Widget for CpuWidget<'a> {
                 // label takes 4 chars ("  XX")
                 // percentage_bar adds 2 chars for brackets "[]"
                 // so we need bar content width = widths[c] - 4 - 2 = widths[c] - 6
-                let width = widths[c as usize].saturating_sub(6);
-                let usage = self.data.cores[i].usage;
-                let text = format!("{:.1}%{:>3.0}°C", usage, self.data.cores[i].temp);
-                let bar = percentage_bar(width, usage, &text);
+                let mut width = widths[c as usize].saturating_sub(6);
+                if self.show_frequency {
+                    width = width.saturating_sub(FREQUENCY_WIDTH);
+                }
+                let core = &self.data.cores[i];
+                let text = format!("{:.1}%{:>3.0}°C", core.usage, core.temp);
+                let bar = self.usage_bar(width, core.usage, &core.breakdown, &text);
                 spans.extend(bar);
+                if self.show_frequency {
+                    spans.push(Span::styled(
+                        format!(" {:>3}G", format_ghz(self.data.cores[i].frequency)),
+                        Style::default().fg(Color::Gray),
+                    ));
+                }
 
                 // TODO: Show something red again if the core temperature gets too high.
                 // Maybe the sidebars of the core itself (the white brackets).
@@ -115,6 +334,62 @@ impl<'a> Widget for CpuWidget<'a> {
             }
             lines.push(Line::from(spans));
         }
+        lines
+    }
+}
+
+impl<'a> Widget for CpuWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut spans = vec![Span::styled("  Total ", Style::default().fg(Color::Cyan))];
+
+        let usage = self.data.usage;
+        let text = format!("{:.1}%", usage);
+        let frequencies = self.data.frequency_range().map(|(min, avg, max)| {
+            format!(
+                " {}/{}/{} GHz",
+                format_ghz(min),
+                format_ghz(avg),
+                format_ghz(max)
+            )
+        });
+        let frequencies_width = frequencies.as_ref().map_or(0, |f| f.len() as u16);
+        let limit = self.limit_text();
+        let limit_width = limit.as_ref().map_or(0, |l| l.chars().count() as u16);
+        let total_bar = self.usage_bar(
+            area.width
+                .saturating_sub(16 + frequencies_width + limit_width),
+            usage,
+            &self.data.breakdown,
+            &text,
+        );
+        spans.extend(total_bar);
+        if let Some(f) = frequencies {
+            spans.push(Span::styled(f, Style::default().fg(Color::Gray)));
+        }
+        if let Some(l) = limit {
+            spans.push(Span::styled(l, Style::default().fg(Color::Yellow)));
+        }
+
+        let mut lines = vec![Line::from(spans).left_aligned()];
+        if self.is_heatmap() {
+            for line in self.heatmap_lines(area.width) {
+                lines.push(match line {
+                    HeatmapLine::Node(node) => Self::node_line(node, area.width),
+                    HeatmapLine::Cores(cpus) => self.heatmap_row(&cpus),
+                });
+            }
+            lines.push(self.readout_line());
+            Paragraph::new(Text::from(lines))
+                .left_aligned()
+                .render(area, buf);
+            return;
+        }
+        for (node, cpus) in self.groups() {
+            if let Some(node) = node {
+                lines.push(Self::node_line(node, area.width));
+            }
+            lines.extend(self.grid_lines(&cpus, area.width));
+        }
 
         let content = Text::from(lines);
 
@@ -126,7 +401,10 @@ impl<'a> Widget for CpuWidget<'a> {
 mod tests {
     use super::CpuSnapshot;
     use super::CpuWidget;
-    use crate::data::cpu::CoreSnapshot;
+    use crate::config::Theme;
+    use crate::data::cpu::{CoreSnapshot, CpuBreakdown};
+    use crate::data::numa::{NumaNode, NumaSnapshot};
+    use ratatui::layout::{Position, Rect};
 
     fn cpu_snap(cores: usize) -> CpuSnapshot {
         CpuSnapshot {
@@ -134,43 +412,100 @@ mod tests {
             cores: vec![
                 CoreSnapshot {
                     usage: 0.0,
-                    temp: 0.0
+                    temp: 0.0,
+                    frequency: 0,
+                    breakdown: CpuBreakdown::default(),
                 };
                 cores
             ],
+            breakdown: CpuBreakdown::default(),
+        }
+    }
+
+    fn widget<'a>(data: &'a CpuSnapshot, theme: &'a Theme) -> CpuWidget<'a> {
+        CpuWidget {
+            data,
+            show_frequency: false,
+            numa: None,
+            max_grid_height: 12,
+            hovered: None,
+            limits: None,
+            theme,
         }
     }
 
     #[test]
     fn test_grid_dimensions() {
-        let widget = CpuWidget { data: &cpu_snap(1) };
-        assert_eq!(widget.grid_dimensions(), (1, 1));
-
-        let widget = CpuWidget { data: &cpu_snap(2) };
-        assert_eq!(widget.grid_dimensions(), (2, 1));
-
-        let widget = CpuWidget { data: &cpu_snap(3) };
-        assert_eq!(widget.grid_dimensions(), (3, 1));
+        let theme = Theme::default();
+        let cases = [
+            (1, (1, 1)),
+            (2, (2, 1)),
+            (3, (3, 1)),
+            (4, (2, 2)),
+            (5, (2, 3)),
+            (12, (3, 4)),
+            (16, (4, 4)),
+            (32, (5, 7)),
+        ];
+        for (cores, dimensions) in cases {
+            let data = cpu_snap(cores);
+            assert_eq!(widget(&data, &theme).grid_dimensions(), dimensions);
+        }
+    }
 
-        let widget = CpuWidget { data: &cpu_snap(4) };
-        assert_eq!(widget.grid_dimensions(), (2, 2));
+    #[test]
+    fn test_heatmap() {
+        let theme = Theme::default();
+        let data = cpu_snap(192);
+        let mut widget = widget(&data, &theme);
+        // a 13 x 15 grid is too tall
+        assert!(widget.is_heatmap());
+        // 32 cells per row on 100 columns
+        assert_eq!(widget.height(100), 6 + 2);
+        let area = Rect::new(0, 10, 100, 8);
+        assert_eq!(widget.core_at(area, Position::new(4 + 3 * 5, 12)), Some(37));
+        assert_eq!(widget.core_at(area, Position::new(4 + 3 * 5 + 2, 12)), None);
+        assert_eq!(widget.core_at(area, Position::new(4, 10)), None);
 
-        let widget = CpuWidget { data: &cpu_snap(5) };
-        assert_eq!(widget.grid_dimensions(), (2, 3));
+        widget.max_grid_height = 13;
+        assert!(!widget.is_heatmap());
+        assert_eq!(widget.core_at(area, Position::new(4, 12)), None);
+    }
 
-        let widget = CpuWidget {
-            data: &cpu_snap(12),
+    #[test]
+    fn test_numa_groups() {
+        let node = |id, cpus: std::ops::Range<usize>| NumaNode {
+            id,
+            cpus: cpus.collect(),
+            memory_total: 0,
+            memory_used: 0,
         };
-        assert_eq!(widget.grid_dimensions(), (3, 4));
-
-        let widget = CpuWidget {
-            data: &cpu_snap(16),
+        let numa = NumaSnapshot {
+            nodes: vec![node(0, 0..8), node(1, 8..16)],
+            gpu_nodes: Vec::new(),
+        };
+        let theme = Theme::default();
+        let data = cpu_snap(16);
+        let mut widget = CpuWidget {
+            numa: Some(&numa),
+            ..widget(&data, &theme)
         };
-        assert_eq!(widget.grid_dimensions(), (4, 4));
+        let groups = widget.groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].0.map(|n| n.id), Some(1));
+        assert_eq!(groups[1].1, (8..16).collect::<Vec<_>>());
+        // the total, and per node its line and a 2 x 4 grid
+        assert!(!widget.is_heatmap());
+        assert_eq!(widget.height(100), 1 + 2 * (1 + 2));
 
-        let widget = CpuWidget {
-            data: &cpu_snap(32),
+        // a single node is shown like no NUMA at all
+        let single = NumaSnapshot {
+            nodes: vec![node(0, 0..16)],
+            gpu_nodes: Vec::new(),
         };
-        assert_eq!(widget.grid_dimensions(), (5, 7));
+        widget.numa = Some(&single);
+        assert_eq!(widget.groups().len(), 1);
+        assert!(widget.groups()[0].0.is_none());
+        assert_eq!(widget.height(100), 1 + 4);
     }
 }
@@ -0,0 +1,67 @@
+use crate::config::Theme;
+use crate::data::disk::{DiskSnapshot, DisksSnapshot};
+use crate::utils::format_bytes_short;
+use crate::widgets::panel::{bar_width, panel_height, panel_rows, panel_table, right_cell};
+use crate::widgets::percentage_bar::percentage_bar;
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    text::{Line, Text},
+    widgets::{Cell, Row},
+};
+
+const CONSTRAINTS: [Constraint; 6] = [
+    Constraint::Length(12),
+    Constraint::Length(10),
+    Constraint::Length(10),
+    Constraint::Length(7),
+    Constraint::Length(7),
+    Constraint::Min(10),
+];
+
+pub struct DiskWidget<'a> {
+    pub data: &'a DisksSnapshot,
+    pub theme: &'a Theme,
+}
+
+impl<'a> DiskWidget<'a> {
+    pub fn height(&self) -> u16 {
+        panel_height(self.data.disks.len())
+    }
+
+    fn create_row(&self, data: &DiskSnapshot, bar_width: u16) -> Row<'static> {
+        Row::new(vec![
+            Cell::from(Text::from(data.name.clone())),
+            right_cell(format!("{}/s", format_bytes_short(data.read_rate))),
+            right_cell(format!("{}/s", format_bytes_short(data.write_rate))),
+            right_cell(format!("{:.0}", data.read_iops)),
+            right_cell(format!("{:.0}", data.write_iops)),
+            Cell::from(Line::from(percentage_bar(
+                bar_width,
+                data.utilization,
+                &format!("{:.0}%", data.utilization),
+            ))),
+        ])
+    }
+}
+
+impl<'a> Widget for DiskWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = [
+            "DISK",
+            "    READ/s",
+            "   WRITE/s",
+            " R IOPS",
+            " W IOPS",
+            "UTIL%",
+        ];
+        let bar_width = bar_width(area.width, &CONSTRAINTS);
+        let rows = panel_rows(&self.data.disks, |d| self.create_row(d, bar_width));
+        Widget::render(
+            panel_table(&header, rows, &CONSTRAINTS, self.theme),
+            area,
+            buf,
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::config::Theme;
+use crate::data::filesystem::{FilesystemSnapshot, FilesystemsSnapshot};
+use crate::utils::format_bytes_short;
+use crate::widgets::panel::{bar_width, panel_height, panel_rows, panel_table, right_cell};
+use crate::widgets::percentage_bar::percentage_bar;
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    text::{Line, Text},
+    widgets::{Cell, Row},
+};
+
+const CONSTRAINTS: [Constraint; 5] = [
+    Constraint::Length(20),
+    Constraint::Length(16),
+    Constraint::Length(7),
+    Constraint::Length(7),
+    Constraint::Min(10),
+];
+
+pub struct FilesystemWidget<'a> {
+    pub data: &'a FilesystemsSnapshot,
+    pub theme: &'a Theme,
+}
+
+impl<'a> FilesystemWidget<'a> {
+    pub fn height(&self) -> u16 {
+        panel_height(self.data.filesystems.len())
+    }
+
+    fn create_row(&self, data: &FilesystemSnapshot, bar_width: u16) -> Row<'static> {
+        Row::new(vec![
+            Cell::from(Text::from(data.path.clone())),
+            Cell::from(Text::from(data.device.clone())),
+            right_cell(format_bytes_short(data.used)),
+            right_cell(format_bytes_short(data.total)),
+            Cell::from(Line::from(percentage_bar(
+                bar_width,
+                data.percent(),
+                &format!("{:.0}%", data.percent()),
+            ))),
+        ])
+    }
+}
+
+impl<'a> Widget for FilesystemWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = ["MOUNT", "DEVICE", "   USED", "  TOTAL", "USE%"];
+        let bar_width = bar_width(area.width, &CONSTRAINTS);
+        let rows = panel_rows(&self.data.filesystems, |f| self.create_row(f, bar_width));
+        Widget::render(
+            panel_table(&header, rows, &CONSTRAINTS, self.theme),
+            area,
+            buf,
+        );
+    }
+}
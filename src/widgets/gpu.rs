@@ -1,5 +1,6 @@
 use crate::constants::BYTES_PER_GB_FLOAT;
-use crate::data::gpu::GpuSnapshot;
+use crate::data::gpu::{GpuDetails, GpuSnapshot};
+use crate::utils::format_bytes_short;
 use crate::widgets::percentage_bar::percentage_bar;
 
 use ratatui::{
@@ -11,15 +12,73 @@ use ratatui::{
 };
 
 pub const GPU_WIDGET_HEIGHT: u16 = 4;
+pub const GPU_WIDGET_EXPANDED_HEIGHT: u16 = 6;
 
 pub struct GpuWidget<'a> {
     pub data: &'a GpuSnapshot,
+    // shows the clocks, PCIe link and encoder/decoder usage too
+    pub expanded: bool,
+}
+
+impl<'a> GpuWidget<'a> {
+    pub fn height(&self) -> u16 {
+        match self.expanded {
+            true => GPU_WIDGET_EXPANDED_HEIGHT,
+            false => GPU_WIDGET_HEIGHT,
+        }
+    }
+
+    fn details_lines(details: &GpuDetails) -> Vec<Line<'static>> {
+        let label = |s: &'static str| Span::styled(s, Style::default().fg(Color::Cyan));
+        let or_na = |v: Option<String>| v.unwrap_or(String::from("N/A"));
+
+        let clock = match (details.graphics_clock, details.max_graphics_clock) {
+            (Some(c), Some(max)) => Some(format!("{} / {} MHz", c, max)),
+            (Some(c), None) => Some(format!("{} MHz", c)),
+            _ => None,
+        };
+        let link = match (details.pcie_gen, details.pcie_width) {
+            (Some(g), Some(w)) => Some(format!("Gen{} x{}", g, w)),
+            _ => None,
+        };
+        let rate = |r: Option<u64>| or_na(r.map(|r| format!("{}/s", format_bytes_short(r))));
+        let percent = |p: Option<u32>| or_na(p.map(|p| format!("{}%", p)));
+
+        vec![
+            Line::from(vec![
+                label("CLK:"),
+                Span::raw(format!(" {}", or_na(clock))),
+                label("   MEM CLK:"),
+                Span::raw(format!(
+                    " {}",
+                    or_na(details.memory_clock.map(|c| format!("{} MHz", c)))
+                )),
+                label("   ENC:"),
+                Span::raw(format!(" {}", percent(details.encoder_utilization))),
+                label("   DEC:"),
+                Span::raw(format!(" {}", percent(details.decoder_utilization))),
+            ])
+            .alignment(Alignment::Left),
+            Line::from(vec![
+                label("PCIe:"),
+                Span::raw(format!(" {}", or_na(link))),
+                label("   TX:"),
+                Span::raw(format!(" {}", rate(details.pcie_tx))),
+                label("   RX:"),
+                Span::raw(format!(" {}", rate(details.pcie_rx))),
+            ])
+            .alignment(Alignment::Left),
+        ]
+    }
 }
 
 impl<'a> Widget for GpuWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let block = Block::bordered()
-            .title(format!(" {}", self.data.name.clone()))
+            .title(match self.data.numa_node {
+                Some(node) => format!(" {} · NUMA {} ", self.data.name, node),
+                None => format!(" {}", self.data.name.clone()),
+            })
             .border_type(BorderType::Rounded)
             .padding(Padding::new(1, 1, 0, 0));
 
@@ -60,6 +119,9 @@ impl<'a> Widget for GpuWidget<'a> {
             ),
         ));
         lines.push(Line::from(spans).alignment(Alignment::Left));
+        if self.expanded {
+            lines.extend(Self::details_lines(&self.data.details));
+        }
 
         let content = Text::from(lines);
         Paragraph::new(content)
@@ -0,0 +1,57 @@
+use crate::data::host::HostSnapshot;
+use crate::utils::format_uptime;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+pub const HEADER_WIDGET_HEIGHT: u16 = 1;
+
+// PSI above this is highlighted, since tasks are waiting a noticeable share of the time
+const PRESSURE_WARNING: f32 = 10.0;
+
+pub struct HeaderWidget<'a> {
+    pub data: &'a HostSnapshot,
+}
+
+impl<'a> Widget for HeaderWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let label = |s: &'static str| Span::styled(s, Style::default().fg(Color::Cyan));
+        let pressure = |p: Option<f32>| match p {
+            Some(p) if p >= PRESSURE_WARNING => {
+                Span::styled(format!(" {:.1}%", p), Style::default().fg(Color::Red))
+            }
+            Some(p) => Span::raw(format!(" {:.1}%", p)),
+            None => Span::raw(" N/A"),
+        };
+        let [one, five, fifteen] = self.data.load_average;
+        let p = &self.data.pressure;
+
+        let spans = vec![
+            Span::styled(
+                format!("  {}", self.data.hostname),
+                Style::default().fg(Color::Yellow),
+            ),
+            label("   Kernel:"),
+            Span::raw(format!(" {}", self.data.kernel)),
+            label("   Up:"),
+            Span::raw(format!(" {}", format_uptime(self.data.uptime))),
+            label("   Load:"),
+            Span::raw(format!(" {:.2} {:.2} {:.2}", one, five, fifteen)),
+            label("   PSI cpu:"),
+            pressure(p.cpu),
+            label(" mem:"),
+            pressure(p.memory),
+            label(" io:"),
+            pressure(p.io),
+        ];
+
+        Paragraph::new(Line::from(spans))
+            .left_aligned()
+            .render(area, buf);
+    }
+}
@@ -14,6 +14,8 @@ use ratatui::{
 pub struct LineGraphWidget<'a> {
     pub data: &'a DataStore,
     pub max_gpu_mem: Option<u64>,
+    pub show_disk: bool,
+    pub show_network: bool,
     pub theme: &'a Theme,
 }
 
@@ -21,21 +23,20 @@ impl<'a> Widget for LineGraphWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let mut datasets = vec![];
 
-        let data: Vec<&StoredSnapshot> = self
-            .data
-            .snapshots
-            .iter()
-            .rev()
-            .take(GRAPH_X_AXIS_WINDOW_IN_SECONDS)
-            .rev()
-            .collect();
+        // x is the time of the snapshot in seconds, with the latest one on the right edge
+        let window = GRAPH_X_AXIS_WINDOW_IN_SECONDS as f64;
+        let latest = self.data.snapshots.last().map(|s| s.time);
+        let x = |s: &StoredSnapshot| match latest {
+            Some(l) => window - l.duration_since(s.time).as_secs_f64(),
+            None => window,
+        };
+        let data: Vec<&StoredSnapshot> =
+            self.data.snapshots.iter().filter(|s| x(s) >= 0.0).collect();
 
         // GPU USE %
         let gpu_use_data: Vec<(f64, f64)> = data
             .iter()
-            .filter_map(|s| s.gpu_use)
-            .enumerate()
-            .map(|(t, g)| (t as f64, g as f64))
+            .filter_map(|s| Some((x(s), s.gpu_use? as f64)))
             .collect();
         if !gpu_use_data.is_empty() {
             datasets.push(
@@ -51,13 +52,9 @@ impl<'a> Widget for LineGraphWidget<'a> {
         // GPU MEM %
         let gpu_mem_data: Vec<(f64, f64)> = data
             .iter()
-            .filter_map(|s| s.gpu_mem_use)
-            .enumerate()
-            .map(|(t, g)| {
-                (
-                    t as f64,
-                    (g as f64 / self.max_gpu_mem.unwrap() as f64 * 100.0),
-                )
+            .filter_map(|s| {
+                let g = s.gpu_mem_use?;
+                Some((x(s), g as f64 / self.max_gpu_mem.unwrap() as f64 * 100.0))
             })
             .collect();
         if !gpu_mem_data.is_empty() {
@@ -72,11 +69,7 @@ impl<'a> Widget for LineGraphWidget<'a> {
         }
 
         // CPU %
-        let cpu_data: Vec<(f64, f64)> = data
-            .iter()
-            .enumerate()
-            .map(|(t, s)| (t as f64, s.cpu_use as f64))
-            .collect();
+        let cpu_data: Vec<(f64, f64)> = data.iter().map(|s| (x(s), s.cpu_use as f64)).collect();
         datasets.push(
             Dataset::default()
                 .name("CPU %")
@@ -87,11 +80,7 @@ impl<'a> Widget for LineGraphWidget<'a> {
         );
 
         // MEM %
-        let mem_data: Vec<(f64, f64)> = data
-            .iter()
-            .enumerate()
-            .map(|(t, s)| (t as f64, s.mem_use * 100.0))
-            .collect();
+        let mem_data: Vec<(f64, f64)> = data.iter().map(|s| (x(s), s.mem_use * 100.0)).collect();
         datasets.push(
             Dataset::default()
                 .name("MEM %")
@@ -101,13 +90,48 @@ impl<'a> Widget for LineGraphWidget<'a> {
                 .data(&mem_data),
         );
 
+        // DISK %
+        let disk_data: Vec<(f64, f64)> = data
+            .iter()
+            .filter_map(|s| Some((x(s), s.disk_use? as f64)))
+            .collect();
+        if self.show_disk && !disk_data.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name("DISK %")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(self.theme.line_graph_disk))
+                    .graph_type(GraphType::Line)
+                    .data(&disk_data),
+            );
+        }
+
+        // NET %
+        let net_data: Vec<(f64, f64)> = data
+            .iter()
+            .filter_map(|s| Some((x(s), s.net_use? as f64)))
+            .collect();
+        if self.show_network && !net_data.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name("NET %")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(self.theme.line_graph_network))
+                    .graph_type(GraphType::Line)
+                    .data(&net_data),
+            );
+        }
+
         Chart::new(datasets)
             .block(Block::bordered().border_type(BorderType::Rounded))
             .x_axis(
                 Axis::default()
                     .style(Style::default().fg(Color::Gray))
-                    .bounds([0.0, 120.0])
-                    .labels(["0", "120"]),
+                    .bounds([0.0, window])
+                    .labels([
+                        format!("-{}s", GRAPH_X_AXIS_WINDOW_IN_SECONDS),
+                        "now".to_string(),
+                    ]),
             )
             .y_axis(
                 Axis::default()
@@ -1,18 +1,147 @@
-use crate::widgets::percentage_bar::percentage_bar;
+use crate::config::Theme;
+use crate::data::cgroup_limits::CgroupLimits;
+use crate::utils::format_bytes_short;
+use crate::widgets::percentage_bar::{percentage_bar, stacked_bar};
 use crate::{constants::BYTES_PER_GB, data::memory::MemorySnapshot};
 
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
     style::{Color, Style},
-    text::{Line, Span},
+    text::{Line, Span, Text},
     widgets::{Paragraph, Widget},
 };
 
 pub const MEMORY_WIDGET_HEIGHT: u16 = 1;
+pub const MEMORY_WIDGET_EXPANDED_HEIGHT: u16 = 3;
 
 pub struct MemoryWidget<'a> {
     pub data: &'a MemorySnapshot,
+    // shows the breakdown of the memory and /dev/shm too
+    pub expanded: bool,
+    // limits of the container or job mltop runs in
+    pub limits: Option<&'a CgroupLimits>,
+    pub theme: &'a Theme,
+}
+
+fn format_gb(bytes: u64) -> String {
+    format!("{:.1}G", bytes as f32 / BYTES_PER_GB as f32)
+}
+
+fn percent(part: u64, total: u64) -> f32 {
+    part as f32 / total as f32 * 100.0
+}
+
+impl<'a> MemoryWidget<'a> {
+    pub fn height(&self) -> u16 {
+        match self.expanded {
+            true => MEMORY_WIDGET_EXPANDED_HEIGHT,
+            false => MEMORY_WIDGET_HEIGHT,
+        }
+    }
+
+    // (used, limit) of our cgroup, if it has a memory limit
+    fn limit(&self) -> Option<(u64, u64)> {
+        let limits = self.limits?;
+        Some((limits.memory_used, limits.memory_max?))
+    }
+
+    fn swap_spans(&self, section_width: u16) -> Vec<Span<'static>> {
+        let mut spans = vec![Span::styled(" Swp", Style::default().fg(Color::Yellow))];
+        let percentage = percent(self.data.used_swap, self.data.total_swap);
+        let text = format!(
+            "{}/{}",
+            format_gb(self.data.used_swap),
+            format_gb(self.data.total_swap)
+        );
+        // width: section - label (4) - brackets (2) = section - 6
+        spans.extend(percentage_bar(
+            section_width.saturating_sub(6),
+            percentage,
+            &text,
+        ));
+        spans
+    }
+
+    // used, buffers, shared and cache stacked in a single bar, like htop
+    fn breakdown_spans(&self, width: u16) -> Vec<Span<'static>> {
+        let d = &self.data.details;
+        let total = self.data.total;
+        let used = self.data.used_by_processes();
+        let segments = [
+            (percent(used, total), self.theme.memory_used),
+            (percent(d.buffers, total), self.theme.memory_buffers),
+            (percent(d.shared, total), self.theme.memory_shared),
+            (percent(d.cache, total), self.theme.memory_cache),
+        ];
+        let text = format!("{}/{}", format_gb(used), format_gb(total));
+
+        let mut spans = vec![Span::styled("  Memory", Style::default().fg(Color::Yellow))];
+        // width: label (8) + brackets (2)
+        spans.extend(stacked_bar(width.saturating_sub(10), &segments, &text));
+        spans
+    }
+
+    fn legend_spans(&self) -> Vec<Span<'static>> {
+        let d = &self.data.details;
+        let entry = |label: &'static str, bytes: u64, color: Color| {
+            [
+                Span::styled(label, Style::default().fg(color)),
+                Span::raw(format!(" {}  ", format_bytes_short(bytes))),
+            ]
+        };
+        let mut spans = vec![Span::raw("          ")];
+        spans.extend(entry(
+            "Used",
+            self.data.used_by_processes(),
+            self.theme.memory_used,
+        ));
+        spans.extend(entry("Buffers", d.buffers, self.theme.memory_buffers));
+        spans.extend(entry("Shared", d.shared, self.theme.memory_shared));
+        spans.extend(entry("Cache", d.cache, self.theme.memory_cache));
+        spans.extend(entry("Available", d.available, Color::Cyan));
+        if let Some((used, max)) = self.limit() {
+            spans.extend([
+                Span::styled("Limit", Style::default().fg(Color::Yellow)),
+                Span::raw(format!(
+                    " {}/{}  ",
+                    format_bytes_short(used),
+                    format_bytes_short(max)
+                )),
+            ]);
+        }
+        if d.huge_pages_total > 0 {
+            spans.extend([
+                Span::styled("HugePages", Style::default().fg(Color::Cyan)),
+                Span::raw(format!(
+                    " {}/{}",
+                    format_bytes_short(d.huge_pages_total - d.huge_pages_free),
+                    format_bytes_short(d.huge_pages_total)
+                )),
+            ]);
+        }
+        spans
+    }
+
+    fn dev_shm_spans(&self, section_width: u16) -> Vec<Span<'static>> {
+        let d = &self.data.details;
+        let mut spans = vec![Span::styled(
+            "  /dev/shm",
+            Style::default().fg(Color::Yellow),
+        )];
+        let text = format!(
+            "{}/{}",
+            format_gb(d.dev_shm_used),
+            format_gb(d.dev_shm_total)
+        );
+        // width: section - label (10) - brackets (2) = section - 12
+        spans.extend(percentage_bar(
+            section_width.saturating_sub(12),
+            percent(d.dev_shm_used, d.dev_shm_total),
+            &text,
+        ));
+        spans
+    }
 }
 
 impl<'a> Widget for MemoryWidget<'a> {
@@ -23,32 +152,42 @@ impl<'a> Widget for MemoryWidget<'a> {
         let mem_section_width = area.width / 2;
         let swap_section_width = total_width.saturating_sub(mem_section_width);
 
-        // memory
+        if self.expanded {
+            let mut shm_line = self.dev_shm_spans(mem_section_width);
+            shm_line.extend(self.swap_spans(swap_section_width));
+            let lines = vec![
+                Line::from(self.breakdown_spans(total_width)),
+                Line::from(self.legend_spans()),
+                Line::from(shm_line),
+            ];
+            Paragraph::new(Text::from(lines))
+                .left_aligned()
+                .render(area, buf);
+            return;
+        }
+
+        // memory, against our cgroup's limit if it has one
         let mut spans = vec![Span::styled("  Memory", Style::default().fg(Color::Yellow))];
-        let percentage = self.data.used as f32 / self.data.total as f32 * 100.0;
-        let text = format!(
-            "{:.1}G/{:.1}G",
-            self.data.used as f32 / BYTES_PER_GB as f32,
-            self.data.total as f32 / BYTES_PER_GB as f32
-        );
+        let (percentage, text) = match self.limit() {
+            Some((used, max)) => (
+                percent(used, max),
+                format!("{}/{} limit", format_gb(used), format_gb(max)),
+            ),
+            None => (
+                percent(self.data.used, self.data.total),
+                format!(
+                    "{}/{}",
+                    format_gb(self.data.used),
+                    format_gb(self.data.total)
+                ),
+            ),
+        };
         // width: section - label (8) - brackets (2) = section - 10
         let mem_bar_width = mem_section_width.saturating_sub(10);
         spans.extend(percentage_bar(mem_bar_width, percentage, &text));
 
         // swap
-        spans.extend(vec![Span::styled(
-            " Swp",
-            Style::default().fg(Color::Yellow),
-        )]);
-        let percentage = self.data.used_swap as f32 / self.data.total_swap as f32 * 100.0;
-        let text = format!(
-            "{:.1}G/{:.1}G",
-            self.data.used_swap as f32 / BYTES_PER_GB as f32,
-            self.data.total_swap as f32 / BYTES_PER_GB as f32
-        );
-        // width: section - label (4) - brackets (2) = section - 6
-        let swap_bar_width = swap_section_width.saturating_sub(6);
-        spans.extend(percentage_bar(swap_bar_width, percentage, &text));
+        spans.extend(self.swap_spans(swap_section_width));
 
         Paragraph::new(Line::from(spans))
             .left_aligned()
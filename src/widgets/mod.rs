@@ -1,8 +1,18 @@
 pub mod action_bar;
 pub mod cpu;
+pub mod disk;
+pub mod filesystem;
 pub mod gpu;
+pub mod header;
 pub mod line_graph;
 pub mod memory;
+pub mod network;
+pub mod panel;
 pub mod percentage_bar;
+pub mod popup;
+pub mod power;
 pub mod process_table;
+pub mod sensors;
+pub mod sparkline;
 pub mod state;
+pub mod user_summary;
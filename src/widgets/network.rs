@@ -0,0 +1,72 @@
+use crate::config::Theme;
+use crate::data::network::{InterfaceSnapshot, NetworkSnapshot};
+use crate::utils::format_bytes_short;
+use crate::widgets::panel::{panel_height, panel_rows, panel_table, right_cell};
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    text::Text,
+    widgets::{Cell, Row},
+};
+
+const CONSTRAINTS: [Constraint; 5] = [
+    Constraint::Length(14),
+    Constraint::Length(10),
+    Constraint::Length(10),
+    Constraint::Length(8),
+    Constraint::Length(6),
+];
+
+pub struct NetworkWidget<'a> {
+    pub data: &'a NetworkSnapshot,
+    pub theme: &'a Theme,
+}
+
+/// Formats a speed in bytes per second as a link speed, e.g. `25Gb/s`
+fn format_link_speed(bytes_per_second: u64) -> String {
+    let megabits = bytes_per_second * 8 / 1_000_000;
+    match megabits >= 1000 {
+        true => format!("{}Gb/s", megabits / 1000),
+        false => format!("{}Mb/s", megabits),
+    }
+}
+
+impl<'a> NetworkWidget<'a> {
+    pub fn height(&self) -> u16 {
+        panel_height(self.data.interfaces.len())
+    }
+
+    fn create_row(&self, data: &InterfaceSnapshot) -> Row<'static> {
+        let unknown = || right_cell("-".to_string());
+        Row::new(vec![
+            Cell::from(Text::from(data.name.clone())),
+            right_cell(format!("{}/s", format_bytes_short(data.rx_rate))),
+            right_cell(format!("{}/s", format_bytes_short(data.tx_rate))),
+            data.speed
+                .map_or_else(unknown, |s| right_cell(format_link_speed(s))),
+            data.utilization()
+                .map_or_else(unknown, |u| right_cell(format!("{:.0}%", u))),
+        ])
+    }
+}
+
+impl<'a> Widget for NetworkWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = [
+            "INTERFACE",
+            "      RX/s",
+            "      TX/s",
+            "    LINK",
+            " UTIL%",
+        ];
+
+        let rows = panel_rows(&self.data.interfaces, |i| self.create_row(i));
+
+        Widget::render(
+            panel_table(&header, rows, &CONSTRAINTS, self.theme),
+            area,
+            buf,
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config::Theme;
+use ratatui::{
+    layout::{Alignment, Constraint},
+    style::Style,
+    text::Text,
+    widgets::{Cell, Row, Table},
+};
+
+// the most rows the disk, network, filesystem and sensors panels show, so that
+// a machine with dozens of mounts or sensors doesn't push the process table away
+pub const MAX_PANEL_ROWS: usize = 8;
+
+/// The height of a panel table with a row per item, and the header
+pub fn panel_height(items: usize) -> u16 {
+    items.min(MAX_PANEL_ROWS) as u16 + 1
+}
+
+/// A row per item, up to `MAX_PANEL_ROWS`. When they don't all fit, the last
+/// row says how many were left out.
+pub fn panel_rows<'a, T>(items: &[T], create_row: impl Fn(&T) -> Row<'a>) -> Vec<Row<'a>> {
+    if items.len() <= MAX_PANEL_ROWS {
+        return items.iter().map(create_row).collect();
+    }
+    let shown = MAX_PANEL_ROWS - 1;
+    let mut rows: Vec<Row> = items[..shown].iter().map(create_row).collect();
+    rows.push(Row::new([Cell::from(format!(
+        "+{} more",
+        items.len() - shown
+    ))]));
+    rows
+}
+
+/// The table of a panel: the header in the panel colors, and the rows in its text
+/// color unless they have their own
+pub fn panel_table<'a>(
+    header: &[&'a str],
+    rows: Vec<Row<'a>>,
+    constraints: &[Constraint],
+    theme: &Theme,
+) -> Table<'a> {
+    let header = header
+        .iter()
+        .map(|h| Cell::from(*h))
+        .collect::<Row>()
+        .style(
+            Style::default()
+                .fg(theme.panel_header_fg)
+                .bg(theme.panel_header_bg),
+        )
+        .height(1);
+    Table::new(rows, constraints.to_vec())
+        .header(header)
+        .style(Style::default().fg(theme.panel_text))
+}
+
+/// A cell aligned to the right, for numbers
+pub fn right_cell(text: String) -> Cell<'static> {
+    Cell::from(Text::from(text).alignment(Alignment::Right))
+}
+
+/// The width of a percentage bar in the last column, which gets what the fixed
+/// columns before it, the spacing between the columns and its brackets leave
+pub fn bar_width(width: u16, constraints: &[Constraint]) -> u16 {
+    let fixed: u16 = constraints
+        .iter()
+        .map(|c| match c {
+            Constraint::Length(length) => *length,
+            _ => 0,
+        })
+        .sum();
+    let spacing = constraints.len().saturating_sub(1) as u16;
+    width.saturating_sub(fixed + spacing + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bar_width, panel_height, panel_rows, MAX_PANEL_ROWS};
+    use ratatui::layout::Constraint;
+    use ratatui::widgets::{Cell, Row};
+
+    #[test]
+    fn test_panel_rows() {
+        let row = |i: &usize| Row::new([Cell::from(i.to_string())]);
+        let items: Vec<usize> = (0..MAX_PANEL_ROWS).collect();
+        assert_eq!(panel_rows(&items, row).len(), MAX_PANEL_ROWS);
+        assert_eq!(panel_height(items.len()), MAX_PANEL_ROWS as u16 + 1);
+
+        let items: Vec<usize> = (0..20).collect();
+        let rows = panel_rows(&items, row);
+        assert_eq!(rows.len(), MAX_PANEL_ROWS);
+        let more = Row::new([Cell::from(format!("+{} more", 20 - MAX_PANEL_ROWS + 1))]);
+        assert_eq!(rows[MAX_PANEL_ROWS - 1], more);
+        assert_eq!(panel_height(items.len()), MAX_PANEL_ROWS as u16 + 1);
+
+        assert!(panel_rows(&[] as &[usize], row).is_empty());
+        assert_eq!(panel_height(0), 1);
+    }
+
+    #[test]
+    fn test_bar_width() {
+        let constraints = [
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Min(10),
+        ];
+        // 22 for the fixed columns, 2 between the columns and 2 for the brackets
+        assert_eq!(bar_width(100, &constraints), 74);
+        assert_eq!(bar_width(20, &constraints), 0);
+    }
+}
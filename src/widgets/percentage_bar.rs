@@ -1,5 +1,5 @@
 use ratatui::{
-    style::{Modifier, Style},
+    style::{Color, Modifier, Style},
     text::Span,
 };
 
@@ -7,11 +7,22 @@ use crate::config::get_config;
 
 const BRACKET_STYLE: Style = Style::new().add_modifier(Modifier::BOLD);
 
-pub fn percentage_bar<'a>(width: u16, perc: f32, text: &str) -> Vec<Span<'a>> {
+/// The color of a bar filled to `perc`, from green to red
+pub fn usage_color(perc: f32) -> Color {
     const HIGH_THRESHOLD: f32 = 90.0;
     const MEDIUM_HIGH_THRESHOLD: f32 = 70.0;
     const MEDIUM_THRESHOLD: f32 = 40.0;
 
+    let theme = &get_config().theme;
+    match perc {
+        p if p > HIGH_THRESHOLD => theme.bar_high_use,
+        p if p > MEDIUM_HIGH_THRESHOLD => theme.bar_medium_high_use,
+        p if p > MEDIUM_THRESHOLD => theme.bar_medium_use,
+        _ => theme.bar_low_use,
+    }
+}
+
+pub fn percentage_bar<'a>(width: u16, perc: f32, text: &str) -> Vec<Span<'a>> {
     let theme = &get_config().theme;
 
     let perc = perc.clamp(0.0, 100.0);
@@ -27,12 +38,7 @@ pub fn percentage_bar<'a>(width: u16, perc: f32, text: &str) -> Vec<Span<'a>> {
     let full_width_usize = ((width_usize as f32) * (perc / 100.0)).round() as usize;
     let bar_width_usize = full_width_usize.min(width_usize.saturating_sub(text_width));
 
-    let color = match perc {
-        p if p > HIGH_THRESHOLD => theme.bar_high_use,
-        p if p > MEDIUM_HIGH_THRESHOLD => theme.bar_medium_high_use,
-        p if p > MEDIUM_THRESHOLD => theme.bar_medium_use,
-        _ => theme.bar_low_use,
-    };
+    let color = usage_color(perc);
 
     let bar = "|".repeat(bar_width_usize);
     let empty_len = width_usize
@@ -56,3 +62,116 @@ pub fn percentage_bar<'a>(width: u16, perc: f32, text: &str) -> Vec<Span<'a>> {
     spans.push(Span::styled("]".to_string(), BRACKET_STYLE));
     spans
 }
+
+/// Like `percentage_bar`, but with consecutive segments of (percentage, color),
+/// like htop's memory bar
+pub fn stacked_bar<'a>(width: u16, segments: &[(f32, Color)], text: &str) -> Vec<Span<'a>> {
+    let theme = &get_config().theme;
+    let empty = Style::default().fg(theme.bar_text).dim();
+    let cells = stacked_cells(width as usize, segments, text, empty);
+
+    let mut spans = vec![Span::styled("[".to_string(), BRACKET_STYLE)];
+    let mut start = 0;
+    for i in 1..=cells.len() {
+        if i == cells.len() || cells[i].1 != cells[start].1 {
+            let content: String = cells[start..i].iter().map(|(c, _)| c).collect();
+            spans.push(Span::styled(content, cells[start].1));
+            start = i;
+        }
+    }
+    spans.push(Span::styled("]".to_string(), BRACKET_STYLE));
+    spans
+}
+
+// the (character, style) of each cell of a stacked bar
+fn stacked_cells(
+    width: usize,
+    segments: &[(f32, Color)],
+    text: &str,
+    empty: Style,
+) -> Vec<(char, Style)> {
+    let mut cells: Vec<(char, Style)> = Vec::with_capacity(width);
+    // the segments end where the running total ends, so the rounding errors
+    // of many small segments don't add up
+    let mut filled = 0.0;
+    for (perc, color) in segments {
+        filled += perc.clamp(0.0, 100.0);
+        let end = (((width as f32) * (filled / 100.0)).round() as usize).min(width);
+        while cells.len() < end {
+            cells.push(('|', Style::default().fg(*color)));
+        }
+    }
+    cells.resize(width, (' ', empty));
+
+    // the text goes on the right, in the color of the segment under it
+    let text: Vec<char> = text.chars().take(width).collect();
+    for (cell, c) in cells[width - text.len()..].iter_mut().zip(text) {
+        cell.0 = c;
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stacked_cells;
+    use ratatui::style::{Color, Style};
+
+    const EMPTY: Style = Style::new();
+
+    fn widths(cells: &[(char, Style)], colors: &[Color]) -> Vec<usize> {
+        colors
+            .iter()
+            .map(|c| cells.iter().filter(|(_, s)| s.fg == Some(*c)).count())
+            .collect()
+    }
+
+    fn text(cells: &[(char, Style)]) -> String {
+        cells.iter().map(|(c, _)| c).collect()
+    }
+
+    #[test]
+    fn test_segment_widths() {
+        let colors = [Color::Green, Color::Blue, Color::Yellow];
+        let segments = [(30.0, colors[0]), (20.0, colors[1]), (50.0, colors[2])];
+        let cells = stacked_cells(20, &segments, "", EMPTY);
+        assert_eq!(cells.len(), 20);
+        assert_eq!(widths(&cells, &colors), [6, 4, 10]);
+
+        // too much is cut at the end of the bar
+        let segments = [(80.0, colors[0]), (80.0, colors[1])];
+        let cells = stacked_cells(20, &segments, "", EMPTY);
+        assert_eq!(widths(&cells, &colors), [16, 4, 0]);
+
+        let cells = stacked_cells(20, &[(25.0, colors[0])], "", EMPTY);
+        assert_eq!(widths(&cells, &colors), [5, 0, 0]);
+        assert_eq!(text(&cells), format!("{}{}", "|".repeat(5), " ".repeat(15)));
+    }
+
+    #[test]
+    fn test_small_segments() {
+        // 0.6 cells each, which would be 10 cells if each was rounded on its own
+        let segments: Vec<(f32, Color)> = (0..10).map(|i| (3.0, Color::Indexed(i))).collect();
+        let cells = stacked_cells(20, &segments, "", EMPTY);
+        assert_eq!(cells.len(), 20);
+        let filled = cells.iter().filter(|(c, _)| *c == '|').count();
+        assert_eq!(filled, 6);
+    }
+
+    #[test]
+    fn test_text() {
+        let segments = [(50.0, Color::Green)];
+        let cells = stacked_cells(10, &segments, "ab", EMPTY);
+        assert_eq!(text(&cells), "|||||   ab");
+        // the text takes the color of the cells under it
+        let cells = stacked_cells(10, &segments, "abcdefg", EMPTY);
+        assert_eq!(text(&cells), "|||abcdefg");
+        assert_eq!(cells[3].1.fg, Some(Color::Green));
+        assert_eq!(cells[5].1, EMPTY);
+
+        // and is cut to the width of the bar
+        let cells = stacked_cells(4, &segments, "56.0G/64.0G", EMPTY);
+        assert_eq!(text(&cells), "56.0");
+        let cells = stacked_cells(0, &segments, "56.0G", EMPTY);
+        assert!(cells.is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+use crate::config::Theme;
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph},
+};
+
+pub const POPUP_WIDTH: u16 = 50;
+pub const POPUP_HEIGHT: u16 = 5;
+
+/// A small centered popup with a single line of user input
+pub struct InputPopupWidget<'a> {
+    pub title: &'a str,
+    pub prompt: &'a str,
+    pub input: &'a str,
+    pub hint: &'a str,
+    pub theme: &'a Theme,
+}
+
+impl<'a> Widget for InputPopupWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Length(POPUP_HEIGHT)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(POPUP_WIDTH)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::bordered()
+            .title(format!(" {} ", self.title))
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(self.theme.action_bar_cmd_bg))
+            .padding(Padding::new(1, 1, 0, 0));
+
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(self.prompt, Style::default().fg(Color::Cyan)),
+                Span::raw(" "),
+                Span::raw(self.input),
+                Span::styled("█", Style::default().fg(Color::Gray)),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(self.hint, Style::default().fg(Color::DarkGray))),
+        ];
+
+        Clear.render(area, buf);
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::data::power::PowerSnapshot;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+pub const POWER_WIDGET_HEIGHT: u16 = 1;
+
+pub struct PowerWidget<'a> {
+    pub data: &'a PowerSnapshot,
+}
+
+impl<'a> Widget for PowerWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let label = |s: &'static str| Span::styled(s, Style::default().fg(Color::Cyan));
+        let watts = |w: Option<f32>| match w {
+            Some(w) => format!(" {:.0} W", w),
+            None => String::from(" N/A"),
+        };
+
+        let mut spans = vec![Span::styled("  Power", Style::default().fg(Color::Yellow))];
+        spans.extend([
+            label("   CPU:"),
+            Span::raw(watts(self.data.cpu)),
+            label("   DRAM:"),
+            Span::raw(watts(self.data.dram)),
+        ]);
+        if self.data.gpu.is_some() {
+            spans.extend([label("   GPU:"), Span::raw(watts(self.data.gpu))]);
+        }
+        spans.extend([
+            label("   System:"),
+            Span::raw(watts(self.data.system())),
+            label("   Session:"),
+            Span::raw(format!(" {:.2} Wh", self.data.energy_wh)),
+        ]);
+
+        Paragraph::new(Line::from(spans))
+            .left_aligned()
+            .render(area, buf);
+    }
+}
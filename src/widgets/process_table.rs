@@ -1,28 +1,47 @@
-use crate::config::Theme;
+use crate::config::process_table::{ColumnConfig, ProcessColumn, ProcessTableConfig};
+use crate::config::{Theme, NUMA_MISMATCH_SAMPLES};
 use crate::constants::BYTES_PER_MB;
+use crate::data::command::target_range;
+use crate::data::numa::NumaSnapshot;
 use crate::data::processes::{Process, ProcessType, ProcessesSnapshot};
-use crate::widgets::state::process_table::{ProcessTableState, ProcessesSortBy};
+use crate::data::store::ProcessHistory;
+use crate::utils::{format_bytes_short, format_cpu_list, format_start_time};
+use crate::widgets::sparkline::sparkline;
+use crate::widgets::state::process_table::ProcessTableState;
 use ratatui::widgets::StatefulWidget;
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Constraint, Rect},
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Cell, Row, Table},
 };
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::ops::Range;
 
-const CONSTRAINTS: [Constraint; 6] = [
-    Constraint::Length(6),
-    Constraint::Length(8),
-    Constraint::Length(5),
-    Constraint::Length(6),
-    Constraint::Length(9),
-    Constraint::Min(10),
-];
+// filters starting with these prefixes match the user or the
+// container instead of the command
+pub const USER_FILTER_PREFIX: &str = "user:";
+pub const CONTAINER_FILTER_PREFIX: &str = "container:";
+
+// shown in front of pinned processes
+const PIN_MARKER: &str = "▶";
+// shown in front of processes whose memory keeps growing
+const LEAK_MARKER: &str = "▲";
+
+// shown in front of the command when threads are shown
+const EXPANDED_PREFIX: &str = "▾ ";
+const COLLAPSED_PREFIX: &str = "▸ ";
+const THREAD_PREFIX: &str = "  └ ";
 
 pub struct ProcessTableWidget<'a> {
     pub data: &'a ProcessesSnapshot,
+    pub history: &'a HashMap<u32, ProcessHistory>,
+    pub config: &'a ProcessTableConfig,
     pub filter_by: Option<&'a str>,
+    // NUMA nodes of the GPUs, to highlight GPU processes running elsewhere
+    pub numa: Option<&'a NumaSnapshot>,
     pub theme: &'a Theme,
 }
 
@@ -30,16 +49,47 @@ impl<'a> StatefulWidget for ProcessTableWidget<'a> {
     type State = ProcessTableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut ProcessTableState) {
-        let header = self.create_header(state);
-
+        let columns = self.visible_columns(state);
         let processes = self.get_processes(state);
 
+        // the marker column only takes up space when something is pinned or leaking
+        let show_markers = processes.iter().any(|p| self.marker(p, state).is_some());
+        let header = self.create_header(&columns, state, show_markers);
         let rows: Vec<Row> = processes
             .iter()
-            .map(|d| self.create_row(d, self.filter_by))
+            .map(|d| {
+                let marker =
+                    show_markers.then(|| self.marker(d, state).unwrap_or(("", Color::Reset)));
+                self.create_row(
+                    d,
+                    &columns,
+                    self.filter_by,
+                    marker,
+                    Self::tree_prefix(d, state),
+                )
+            })
             .collect();
 
-        Table::new(rows, CONSTRAINTS)
+        let mut constraints: Vec<Constraint> = columns.iter().map(|c| c.constraint()).collect();
+        if show_markers {
+            constraints.insert(0, Constraint::Length(1));
+        }
+
+        // lays out the columns like the table does, to remember where the headers are
+        let cells = Layout::horizontal(&constraints)
+            .flex(Flex::Start)
+            .spacing(1)
+            .split(area);
+        state.area = area;
+        state.header_cells = cells
+            .iter()
+            .skip(show_markers as usize)
+            .zip(&columns)
+            .map(|(rect, c)| (Rect { height: 1, ..*rect }, c.column()))
+            .collect();
+        state.row_count = processes.len();
+
+        Table::new(rows, constraints)
             .header(header)
             .row_highlight_style(
                 Style::new()
@@ -51,25 +101,61 @@ impl<'a> StatefulWidget for ProcessTableWidget<'a> {
 }
 
 impl<'a> ProcessTableWidget<'a> {
-    fn create_header(&self, state: &ProcessTableState) -> Row<'static> {
+    /// The configured columns, plus the disk I/O columns if they were toggled on
+    /// and aren't configured already. These go right before the command.
+    pub fn visible_columns(&self, state: &ProcessTableState) -> Vec<ColumnConfig> {
+        let mut columns = self.config.columns.to_vec();
+        if state.show_io {
+            let position = columns
+                .iter()
+                .position(|c| c.column() == ProcessColumn::Command)
+                .unwrap_or(columns.len());
+            for column in [ProcessColumn::Write, ProcessColumn::Read] {
+                if !columns.iter().any(|c| c.column() == column) {
+                    columns.insert(position, ColumnConfig::Name(column));
+                }
+            }
+        }
+        columns
+    }
+
+    fn create_header(
+        &self,
+        columns: &[ColumnConfig],
+        state: &ProcessTableState,
+        show_markers: bool,
+    ) -> Row<'static> {
         let header_style = Style::default()
             .fg(self.theme.processes_header_fg)
             .bg(self.theme.processes_header_bg);
-        let (cpu, mem) = match &state.sort_by {
-            ProcessesSortBy::CPU => ("▽CPU%", "  MEM%"),
-            ProcessesSortBy::MEM => (" CPU%", " ▽MEM%"),
-        };
 
-        ["   pid", "type", cpu, mem, "   MEMORY", "Command"]
+        let titles = columns.iter().map(|c| {
+            let column = c.column();
+            let title = match column == state.sort_by {
+                true => format!("▽{}", column.title()),
+                false => column.title().to_string(),
+            };
+            Cell::from(Line::from(title).alignment(column.alignment()))
+        });
+        show_markers
+            .then(|| Cell::from(""))
             .into_iter()
-            .map(Cell::from)
+            .chain(titles)
             .collect::<Row>()
             .style(header_style)
             .height(1)
     }
 
-    fn create_row(&self, data: &Process, filter_by: Option<&'a str>) -> Row<'_> {
+    fn create_row(
+        &self,
+        data: &Process,
+        columns: &[ColumnConfig],
+        filter_by: Option<&'a str>,
+        marker: Option<(&'static str, Color)>,
+        tree_prefix: &'static str,
+    ) -> Row<'_> {
         let color = match data.type_ {
+            _ if self.is_numa_mismatch(data) => self.theme.processes_numa_mismatch,
             ProcessType::GpuGraphic => self.theme.processes_gpu_graphic,
             ProcessType::GpuCompute => self.theme.processes_gpu_compute,
             ProcessType::UserThread => self.theme.processes_thread,
@@ -77,61 +163,185 @@ impl<'a> ProcessTableWidget<'a> {
             _ => self.theme.processes_cpu,
         };
 
-        let cpu_text_color = if data.cpu_usage < 0.05 {
-            Color::DarkGray
-        } else {
-            color
-        };
-        let mem_text_color = if data.memory_usage < 0.05 {
-            Color::DarkGray
-        } else {
-            color
-        };
-
-        Row::new(vec![
-            Cell::from(Text::from(data.pid.to_string()).alignment(Alignment::Right)),
-            Cell::from(Text::from(data.type_.to_string())),
-            Cell::from(
-                Line::from(vec![
-                    Span::styled(
-                        format!("{:.1}", data.cpu_usage)
-                            .chars()
-                            .take(4)
-                            .collect::<String>(),
-                        Style::default().fg(cpu_text_color),
-                    ),
-                    Span::styled("%", Style::default().fg(Color::DarkGray)),
-                ])
-                .alignment(Alignment::Right),
+        let marker = marker.map(|(m, c)| Cell::from(m).style(Style::default().fg(c)));
+        let cells: Vec<Cell> = marker
+            .into_iter()
+            .chain(
+                columns
+                    .iter()
+                    .map(|c| self.create_cell(c, data, color, filter_by, tree_prefix)),
+            )
+            .collect();
+
+        Row::new(cells).style(Style::default().fg(color))
+    }
+
+    // pinned processes are marked over leaking ones
+    fn marker(&self, data: &Process, state: &ProcessTableState) -> Option<(&'static str, Color)> {
+        if self.is_pinned(data, state) {
+            return Some((PIN_MARKER, self.theme.processes_pinned));
+        }
+        self.memory_growth(data.pid)
+            .map(|_| (LEAK_MARKER, self.theme.processes_leak))
+    }
+
+    // a GPU process that isn't allowed on the NUMA nodes of its GPUs, or that kept
+    // running on another node, as a single sample is often just the scheduler passing by
+    fn is_numa_mismatch(&self, data: &Process) -> bool {
+        let Some(numa) = self.numa else {
+            return false;
+        };
+        let cpus = numa.gpu_cpus(&data.gpus);
+        if cpus.is_empty() {
+            return false;
+        }
+        let cpus = cpus.as_slice();
+        let off_node = !data.affinity.is_empty() && !data.affinity.iter().any(|c| cpus.contains(c));
+        off_node
+            || self
+                .history
+                .get(&data.pid)
+                .is_some_and(|h| h.ran_outside(cpus, NUMA_MISMATCH_SAMPLES))
+    }
+
+    fn create_cell(
+        &self,
+        config: &ColumnConfig,
+        data: &Process,
+        color: Color,
+        filter_by: Option<&'a str>,
+        tree_prefix: &'static str,
+    ) -> Cell<'_> {
+        let column = config.column();
+        let text = |s: String| Cell::from(Text::from(s).alignment(column.alignment()));
+        match column {
+            ProcessColumn::Pid => text(data.pid.to_string()),
+            ProcessColumn::User => text(data.user.clone()),
+            ProcessColumn::Type => text(data.type_.to_string()),
+            ProcessColumn::Nice => text(data.nice.to_string()),
+            ProcessColumn::Affinity => text(format_cpu_list(&data.affinity)),
+            ProcessColumn::Cpu => Self::create_percentage_cell(
+                format!("{:.1}", data.cpu_usage)
+                    .chars()
+                    .take(4)
+                    .collect::<String>(),
+                data.cpu_usage,
+                color,
             ),
-            Cell::from(
-                Line::from(vec![
-                    Span::styled(
-                        format!("{:.1}", data.memory_usage),
-                        Style::default().fg(mem_text_color),
-                    ),
-                    Span::styled("%", Style::default().fg(Color::DarkGray)),
-                ])
-                .alignment(Alignment::Right),
+            ProcessColumn::Mem => Self::create_percentage_cell(
+                format!("{:.1}", data.memory_usage),
+                data.memory_usage,
+                color,
             ),
-            Cell::from(
-                Line::from(vec![
-                    Span::from(format!("{:.0}", data.memory / BYTES_PER_MB)),
-                    Span::styled("MiB", Style::default().fg(Color::DarkGray)),
-                ])
-                .alignment(Alignment::Right),
+            ProcessColumn::Memory => Self::create_mib_cell(data.memory),
+            ProcessColumn::Growth => match self.memory_growth(data.pid) {
+                Some(g) => Cell::from(
+                    Line::from(Span::styled(
+                        format!("+{}/h", format_bytes_short((g * 3600.0) as u64)),
+                        Style::default().fg(self.theme.processes_leak),
+                    ))
+                    .alignment(Alignment::Right),
+                ),
+                None => Cell::from(""),
+            },
+            ProcessColumn::GpuMemory => Self::create_mib_cell(data.gpu_memory),
+            ProcessColumn::Read => Self::create_rate_cell(data.read_rate, color),
+            ProcessColumn::Write => Self::create_rate_cell(data.write_rate, color),
+            ProcessColumn::State => text(data.state.to_string()),
+            ProcessColumn::StartTime => text(format_start_time(data.start_time)),
+            ProcessColumn::Threads => text(data.threads.to_string()),
+            ProcessColumn::Framework => match data.framework {
+                Some(f) => Cell::from(Span::styled(
+                    f.to_string(),
+                    Style::default().fg(self.theme.processes_bin_name),
+                )),
+                None => Cell::from(""),
+            },
+            ProcessColumn::CpuHistory => {
+                let values: Vec<f64> = self
+                    .history(data.pid)
+                    .map(|h| h.cpu_use.iter().map(|c| *c as f64).collect())
+                    .unwrap_or_default();
+                // scaled to at least one full core, so idle processes don't look busy
+                let max = values.iter().cloned().fold(100.0, f64::max);
+                text(sparkline(&values, max, config.width() as usize))
+            }
+            ProcessColumn::GpuMemoryHistory => {
+                let values: Vec<f64> = self
+                    .history(data.pid)
+                    .map(|h| h.gpu_mem_use.iter().map(|m| *m as f64).collect())
+                    .unwrap_or_default();
+                let max = values.iter().cloned().fold(0.0, f64::max);
+                text(sparkline(&values, max, config.width() as usize))
+            }
+            ProcessColumn::Container => text(
+                data.container
+                    .as_ref()
+                    .map(|c| c.to_string())
+                    .unwrap_or_default(),
             ),
-            self.create_cmd_cell(&data.command, color, filter_by),
-        ])
-        .style(Style::default().fg(color))
+            ProcessColumn::Command => {
+                self.create_cmd_cell(&data.command, color, filter_by, tree_prefix)
+            }
+        }
+    }
+
+    fn history(&self, pid: u32) -> Option<&ProcessHistory> {
+        self.history.get(&pid)
+    }
+
+    // bytes per second, for processes whose memory keeps growing
+    fn memory_growth(&self, pid: u32) -> Option<f64> {
+        self.history(pid)?.memory_growth
+    }
+
+    fn create_percentage_cell(text: String, value: f32, color: Color) -> Cell<'static> {
+        let text_color = if value < 0.05 { Color::DarkGray } else { color };
+        Cell::from(
+            Line::from(vec![
+                Span::styled(text, Style::default().fg(text_color)),
+                Span::styled("%", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Right),
+        )
+    }
+
+    fn create_mib_cell(bytes: u64) -> Cell<'static> {
+        Cell::from(
+            Line::from(vec![
+                Span::from(format!("{:.0}", bytes / BYTES_PER_MB)),
+                Span::styled("MiB", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Right),
+        )
+    }
+
+    fn create_rate_cell(rate: u64, color: Color) -> Cell<'static> {
+        let text_color = if rate == 0 { Color::DarkGray } else { color };
+        Cell::from(
+            Line::from(Span::styled(
+                format_bytes_short(rate),
+                Style::default().fg(text_color),
+            ))
+            .alignment(Alignment::Right),
+        )
     }
 
     // creates a Cell with the process command:
-    // - highlights the `bin` part of the command with Magenta text
+    // - highlights the `bin` part of the command with Magenta text. For interpreter
+    //   launches (python, torchrun, ...) this is the script or module being run
     // - highlights the `filter_by` matching string with a green background
-    fn create_cmd_cell(&self, cmd: &str, color: Color, filter_by: Option<&str>) -> Cell<'_> {
-        let bin_start = cmd.rfind('/').map(|i| i + 1).unwrap_or(0);
-        let bin_end = cmd.find(' ').unwrap_or(cmd.len());
+    fn create_cmd_cell(
+        &self,
+        cmd: &str,
+        color: Color,
+        filter_by: Option<&str>,
+        tree_prefix: &'static str,
+    ) -> Cell<'_> {
+        let Range {
+            start: bin_start,
+            end: bin_end,
+        } = target_range(cmd);
 
         let (match_start, match_end) = match filter_by {
             Some(s) => match cmd.find(s) {
@@ -145,10 +355,12 @@ impl<'a> ProcessTableWidget<'a> {
         cuts.sort_unstable();
         cuts.dedup();
 
-        let spans: Vec<Span> = cuts
-            .windows(2)
-            .filter(|w| w[0] != w[1])
-            .map(|w| {
+        let prefix = Span::styled(
+            tree_prefix,
+            Style::default().fg(self.theme.processes_thread),
+        );
+        let spans: Vec<Span> = std::iter::once(prefix)
+            .chain(cuts.windows(2).filter(|w| w[0] != w[1]).map(|w| {
                 let (s, e) = (w[0], w[1]);
                 let text = cmd[s..e].to_string();
                 let mut style = Style::default().fg(color);
@@ -166,38 +378,179 @@ impl<'a> ProcessTableWidget<'a> {
                 }
 
                 Span::styled(text, style)
-            })
+            }))
             .collect();
 
         Cell::from(Line::from(spans))
     }
 
+    /// The processes in the order they're shown: the pinned ones first, whatever
+    /// the filter, then the rest filtered and sorted. When threads are shown, the
+    /// threads of each process follow it, unless it's collapsed.
     pub fn get_processes(&'a self, state: &mut ProcessTableState) -> Vec<Process> {
-        let mut processes = Self::filter_processes(&self.data.processes, self.filter_by);
-        Self::sort_processes(&mut processes, &state.sort_by);
+        let (threads, processes): (Vec<Process>, Vec<Process>) = self
+            .data
+            .processes
+            .iter()
+            .cloned()
+            .partition(|p| p.owner.is_some());
+        let (mut pinned, rest): (Vec<Process>, Vec<Process>) = processes
+            .into_iter()
+            .partition(|p| self.is_pinned(p, state));
+        // kernel threads matching a pattern are hidden like any other thread,
+        // but explicitly pinned ones stay
+        pinned.retain(|p| state.show_threads || !p.is_thread() || state.pinned.contains(&p.pid));
+        Self::sort_processes(&mut pinned, &state.sort_by, self.history);
+
+        let mut processes = Self::filter_processes(&rest, self.filter_by);
+        Self::sort_processes(&mut processes, &state.sort_by, self.history);
         Self::filter_threads(&mut processes, state.show_threads);
-        processes
+
+        pinned.extend(processes);
+        match state.show_threads {
+            true => self.nest_threads(pinned, threads, state),
+            false => pinned,
+        }
+    }
+
+    // puts the threads right after the process they belong to
+    fn nest_threads(
+        &self,
+        processes: Vec<Process>,
+        threads: Vec<Process>,
+        state: &ProcessTableState,
+    ) -> Vec<Process> {
+        let mut by_owner: HashMap<u32, Vec<Process>> = HashMap::new();
+        for thread in threads {
+            if let Some(owner) = thread.owner {
+                by_owner.entry(owner).or_default().push(thread);
+            }
+        }
+
+        let mut nested = Vec::with_capacity(processes.len());
+        for process in processes {
+            let threads = match state.collapsed.contains(&process.pid) {
+                true => None,
+                false => by_owner.remove(&process.pid),
+            };
+            nested.push(process);
+            if let Some(mut threads) = threads {
+                Self::sort_processes(&mut threads, &state.sort_by, self.history);
+                nested.extend(threads);
+            }
+        }
+        nested
+    }
+
+    // the indentation of threads, and whether processes with threads are collapsed
+    fn tree_prefix(process: &Process, state: &ProcessTableState) -> &'static str {
+        if !state.show_threads {
+            return "";
+        }
+        match process.owner {
+            Some(_) => THREAD_PREFIX,
+            None if process.is_thread() || process.threads <= 1 => "",
+            None if state.collapsed.contains(&process.pid) => COLLAPSED_PREFIX,
+            None => EXPANDED_PREFIX,
+        }
+    }
+
+    fn is_pinned(&self, process: &Process, state: &ProcessTableState) -> bool {
+        // threads of a process are shown under it, so they can't be pinned themselves
+        if process.owner.is_some() {
+            return false;
+        }
+        state.pinned.contains(&process.pid) || self.config.pins(&process.command)
     }
 
     pub fn filter_processes(processes: &[Process], filter_by: Option<&str>) -> Vec<Process> {
         match filter_by {
             Some(s) => processes
                 .iter()
-                .filter(|p| p.command.contains(s))
+                .filter(|p| Self::matches_filter(p, s))
                 .cloned()
                 .collect(),
             None => processes.to_vec(),
         }
     }
 
-    pub fn sort_processes(processes: &mut [Process], sort_by: &ProcessesSortBy) {
+    fn matches_filter(process: &Process, filter_by: &str) -> bool {
+        if let Some(user) = filter_by.strip_prefix(USER_FILTER_PREFIX) {
+            return process.user.contains(user);
+        }
+        if let Some(container) = filter_by.strip_prefix(CONTAINER_FILTER_PREFIX) {
+            return process
+                .container
+                .as_ref()
+                .is_some_and(|c| c.to_string().contains(container));
+        }
+        process.command.contains(filter_by)
+    }
+
+    pub fn sort_processes(
+        processes: &mut [Process],
+        sort_by: &ProcessColumn,
+        histories: &HashMap<u32, ProcessHistory>,
+    ) {
         match sort_by {
-            ProcessesSortBy::CPU => {
+            ProcessColumn::Pid => processes.sort_by_key(|p| p.pid),
+            ProcessColumn::User => processes.sort_by(|a, b| {
+                a.user
+                    .cmp(&b.user)
+                    .then_with(|| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap())
+            }),
+            ProcessColumn::Type => processes.sort_by_key(|p| p.type_.to_string()),
+            ProcessColumn::Nice => processes.sort_by_key(|p| p.nice),
+            // processes pinned to fewer cpus first
+            ProcessColumn::Affinity => processes.sort_by_key(|p| p.affinity.len()),
+            ProcessColumn::Cpu => {
                 processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap())
             }
-            ProcessesSortBy::MEM => {
+            ProcessColumn::Mem => {
                 processes.sort_by(|a, b| b.memory_usage.partial_cmp(&a.memory_usage).unwrap())
             }
+            ProcessColumn::Memory => processes.sort_by_key(|p| Reverse(p.memory)),
+            // fastest growing first
+            ProcessColumn::Growth => {
+                let growth = |p: &Process| {
+                    histories
+                        .get(&p.pid)
+                        .and_then(|h| h.memory_growth)
+                        .unwrap_or(0.0)
+                };
+                processes.sort_by(|a, b| growth(b).partial_cmp(&growth(a)).unwrap())
+            }
+            ProcessColumn::GpuMemory => processes.sort_by_key(|p| Reverse(p.gpu_memory)),
+            ProcessColumn::Read => processes.sort_by_key(|p| Reverse(p.read_rate)),
+            ProcessColumn::Write => processes.sort_by_key(|p| Reverse(p.write_rate)),
+            ProcessColumn::State => processes.sort_by_key(|p| p.state),
+            // newest processes first
+            ProcessColumn::StartTime => processes.sort_by_key(|p| Reverse(p.start_time)),
+            ProcessColumn::Threads => processes.sort_by_key(|p| Reverse(p.threads)),
+            // processes with a framework first
+            ProcessColumn::Framework => processes.sort_by_key(|p| p.framework.is_none()),
+            // by the average over the stored history
+            ProcessColumn::CpuHistory => {
+                let mean = |p: &Process| match histories.get(&p.pid) {
+                    Some(h) => h.cpu_use.iter().sum::<f32>() / h.cpu_use.len() as f32,
+                    None => 0.0,
+                };
+                processes.sort_by(|a, b| mean(b).partial_cmp(&mean(a)).unwrap())
+            }
+            ProcessColumn::GpuMemoryHistory => {
+                let mean = |p: &Process| match histories.get(&p.pid) {
+                    Some(h) => h.gpu_mem_use.iter().sum::<u64>() / h.gpu_mem_use.len() as u64,
+                    None => 0,
+                };
+                processes.sort_by_key(|p| Reverse(mean(p)))
+            }
+            ProcessColumn::Container => processes.sort_by_key(|p| {
+                (
+                    p.container.is_none(),
+                    p.container.as_ref().map(|c| c.to_string()),
+                )
+            }),
+            ProcessColumn::Command => processes.sort_by(|a, b| a.command.cmp(&b.command)),
         };
     }
 
@@ -211,3 +564,257 @@ impl<'a> ProcessTableWidget<'a> {
         Some(self.get_processes(state).get(n)?.pid)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ProcessTableWidget, COLLAPSED_PREFIX, EXPANDED_PREFIX, THREAD_PREFIX};
+    use crate::config::process_table::{ProcessColumn, ProcessTableConfig};
+    use crate::config::{MemoryLeakConfig, Theme, NUMA_MISMATCH_SAMPLES};
+    use crate::data::numa::{NumaNode, NumaSnapshot};
+    use crate::data::processes::{Process, ProcessType, ProcessesSnapshot};
+    use crate::data::store::DataStore;
+    use crate::widgets::state::process_table::ProcessTableState;
+    use std::collections::{HashMap, HashSet};
+    use std::time::Instant;
+
+    fn process(pid: u32, command: &str, cpu_usage: f32) -> Process {
+        Process {
+            pid,
+            type_: ProcessType::Cpu,
+            user: String::from("alice"),
+            command: String::from(command),
+            cpu_usage,
+            memory: 0,
+            memory_usage: 0.0,
+            gpu_memory: 0,
+            gpus: Vec::new(),
+            nice: 0,
+            affinity: vec![0],
+            last_cpu: None,
+            read_rate: 0,
+            write_rate: 0,
+            state: 'R',
+            start_time: 0,
+            threads: 1,
+            framework: None,
+            container: None,
+            owner: None,
+        }
+    }
+
+    fn thread(pid: u32, owner: u32, cpu_usage: f32) -> Process {
+        Process {
+            type_: ProcessType::UserThread,
+            owner: Some(owner),
+            ..process(pid, "python train.py", cpu_usage)
+        }
+    }
+
+    fn pids(
+        data: &ProcessesSnapshot,
+        config: &ProcessTableConfig,
+        filter_by: Option<&str>,
+        state: &mut ProcessTableState,
+    ) -> Vec<u32> {
+        let history = HashMap::new();
+        let theme = Theme::default();
+        let widget = ProcessTableWidget {
+            data,
+            history: &history,
+            config,
+            filter_by,
+            numa: None,
+            theme: &theme,
+        };
+        widget.get_processes(state).iter().map(|p| p.pid).collect()
+    }
+
+    #[test]
+    fn test_pinned_first() {
+        let data = ProcessesSnapshot {
+            processes: vec![
+                process(1, "bash", 90.0),
+                process(2, "python train.py", 10.0),
+                process(3, "vim", 50.0),
+                process(4, "python eval.py", 20.0),
+            ],
+        };
+        let config = ProcessTableConfig {
+            pinned: vec![String::from("train.py")],
+            ..ProcessTableConfig::default()
+        };
+        let mut state = ProcessTableState::default();
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 1, 3, 4]);
+
+        // the pinned processes are sorted among themselves and ignore the filter
+        state.toggle_pin(4);
+        assert_eq!(pids(&data, &config, None, &mut state), [4, 2, 1, 3]);
+        assert_eq!(pids(&data, &config, Some("vim"), &mut state), [4, 2, 3]);
+
+        state.sort_by = ProcessColumn::Pid;
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn test_retain_alive() {
+        let mut state = ProcessTableState::default();
+        state.toggle_pin(1);
+        state.toggle_pin(2);
+        state.toggle_collapsed(2);
+        state.toggle_collapsed(3);
+
+        state.retain_alive(&HashSet::from([2]));
+        assert_eq!(state.pinned, HashSet::from([2]));
+        assert_eq!(state.collapsed, HashSet::from([2]));
+
+        // nothing is left once every process exited
+        state.retain_alive(&HashSet::new());
+        assert!(state.pinned.is_empty());
+        assert!(state.collapsed.is_empty());
+    }
+
+    #[test]
+    fn test_nest_threads() {
+        let data = ProcessesSnapshot {
+            processes: vec![
+                Process {
+                    threads: 3,
+                    ..process(1, "python train.py", 50.0)
+                },
+                process(2, "bash", 90.0),
+                thread(11, 1, 10.0),
+                thread(12, 1, 30.0),
+            ],
+        };
+        let config = ProcessTableConfig::default();
+        let mut state = ProcessTableState::default();
+        // threads are only listed when shown
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 1]);
+
+        // then they're sorted under the process they belong to
+        state.toggle_show_threads();
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 1, 12, 11]);
+
+        // and go with it when it's filtered out
+        assert_eq!(pids(&data, &config, Some("bash"), &mut state), [2]);
+    }
+
+    #[test]
+    fn test_toggle_collapsed() {
+        let owner = Process {
+            threads: 2,
+            ..process(1, "python train.py", 50.0)
+        };
+        let data = ProcessesSnapshot {
+            processes: vec![owner.clone(), process(2, "bash", 90.0), thread(11, 1, 10.0)],
+        };
+        let config = ProcessTableConfig::default();
+        let mut state = ProcessTableState::default();
+        state.toggle_show_threads();
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 1, 11]);
+        assert_eq!(
+            ProcessTableWidget::tree_prefix(&owner, &state),
+            EXPANDED_PREFIX
+        );
+        assert_eq!(
+            ProcessTableWidget::tree_prefix(&data.processes[2], &state),
+            THREAD_PREFIX
+        );
+        // single threaded processes have nothing to expand
+        assert_eq!(
+            ProcessTableWidget::tree_prefix(&data.processes[1], &state),
+            ""
+        );
+
+        state.toggle_collapsed(1);
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 1]);
+        assert_eq!(
+            ProcessTableWidget::tree_prefix(&owner, &state),
+            COLLAPSED_PREFIX
+        );
+
+        state.toggle_collapsed(1);
+        assert_eq!(pids(&data, &config, None, &mut state), [2, 1, 11]);
+
+        // no prefixes without the threads
+        state.toggle_show_threads();
+        assert_eq!(ProcessTableWidget::tree_prefix(&owner, &state), "");
+    }
+
+    #[test]
+    fn test_numa_mismatch() {
+        let node = |id, cpus| NumaNode {
+            id,
+            cpus,
+            memory_total: 0,
+            memory_used: 0,
+        };
+        let numa = NumaSnapshot {
+            nodes: vec![node(0, vec![0, 1]), node(1, vec![2, 3])],
+            gpu_nodes: vec![Some(0), Some(1)],
+        };
+        let gpu_process = |last_cpu, affinity| Process {
+            gpu_memory: 1,
+            gpus: vec![0],
+            last_cpu: Some(last_cpu),
+            affinity,
+            ..process(1, "python train.py", 50.0)
+        };
+        let mut store = DataStore::new();
+        let config = ProcessTableConfig::default();
+        let theme = Theme::default();
+        let is_mismatch = |store: &DataStore, process: &Process| {
+            let data = ProcessesSnapshot { processes: vec![] };
+            ProcessTableWidget {
+                data: &data,
+                history: &store.processes,
+                config: &config,
+                filter_by: None,
+                numa: Some(&numa),
+                theme: &theme,
+            }
+            .is_numa_mismatch(process)
+        };
+
+        // not allowed on the GPU's node at all
+        assert!(is_mismatch(&store, &gpu_process(2, vec![2, 3])));
+        // allowed, and only just moved off the node
+        let process = gpu_process(2, vec![0, 1, 2, 3]);
+        let save = |store: &mut DataStore, process: &Process| {
+            let snapshot = ProcessesSnapshot {
+                processes: vec![process.clone()],
+            };
+            store.save_processes(&snapshot, &MemoryLeakConfig::default(), Instant::now());
+        };
+        for _ in 1..NUMA_MISMATCH_SAMPLES {
+            save(&mut store, &process);
+        }
+        assert!(!is_mismatch(&store, &process));
+        // kept running off the node
+        save(&mut store, &process);
+        assert!(is_mismatch(&store, &process));
+        // back on the node
+        save(&mut store, &gpu_process(0, vec![0, 1, 2, 3]));
+        assert!(!is_mismatch(&store, &process));
+
+        // the node of the GPU the process runs on is the one that counts
+        let second_gpu = Process {
+            gpus: vec![1],
+            ..gpu_process(2, vec![2, 3])
+        };
+        assert!(!is_mismatch(&store, &second_gpu));
+        let both_gpus = Process {
+            gpus: vec![0, 1],
+            ..gpu_process(2, vec![2, 3])
+        };
+        assert!(!is_mismatch(&store, &both_gpus));
+
+        // processes without a GPU don't care
+        let cpu_process = Process {
+            gpu_memory: 0,
+            gpus: Vec::new(),
+            ..gpu_process(2, vec![2, 3])
+        };
+        assert!(!is_mismatch(&store, &cpu_process));
+    }
+}
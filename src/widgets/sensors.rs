@@ -0,0 +1,65 @@
+use crate::config::Theme;
+use crate::data::sensors::{SensorSnapshot, SensorsSnapshot};
+use crate::widgets::panel::{panel_height, panel_rows, panel_table, right_cell};
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Style},
+    text::Text,
+    widgets::{Cell, Row},
+};
+
+const CONSTRAINTS: [Constraint; 4] = [
+    Constraint::Length(44),
+    Constraint::Length(7),
+    Constraint::Length(7),
+    Constraint::Length(7),
+];
+
+pub struct SensorsWidget<'a> {
+    pub data: &'a SensorsSnapshot,
+    pub theme: &'a Theme,
+}
+
+impl<'a> SensorsWidget<'a> {
+    pub fn height(&self) -> u16 {
+        panel_height(self.data.sensors.len())
+    }
+
+    // colored by how close the sensor is to its critical temperature
+    fn color(&self, data: &SensorSnapshot) -> Color {
+        match data.closeness() {
+            Some(c) if c >= 0.95 => self.theme.bar_high_use,
+            Some(c) if c >= 0.85 => self.theme.bar_medium_high_use,
+            Some(c) if c >= 0.7 => self.theme.bar_medium_use,
+            _ => self.theme.panel_text,
+        }
+    }
+
+    fn create_row(&self, data: &SensorSnapshot) -> Row<'static> {
+        let temperature =
+            |t: Option<f32>| right_cell(t.map_or("N/A".to_string(), |t| format!("{:.0}°C", t)));
+        Row::new(vec![
+            Cell::from(Text::from(data.label.clone())),
+            temperature(data.temperature),
+            temperature(data.max),
+            temperature(data.critical),
+        ])
+        .style(Style::default().fg(self.color(data)))
+    }
+}
+
+impl<'a> Widget for SensorsWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = ["SENSOR", "   TEMP", "    MAX", "   CRIT"];
+
+        let rows = panel_rows(&self.data.sensors, |s| self.create_row(s));
+
+        Widget::render(
+            panel_table(&header, rows, &CONSTRAINTS, self.theme),
+            area,
+            buf,
+        );
+    }
+}
@@ -0,0 +1,30 @@
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Renders the last `width` values as a line of bar characters scaled to `max`,
+/// right aligned so the most recent value is always at the end
+pub fn sparkline(values: &[f64], max: f64, width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let mut line = " ".repeat(width - values.len());
+    for value in values {
+        let level = match max > 0.0 {
+            true => ((value / max).clamp(0.0, 1.0) * (BARS.len() - 1) as f64).round(),
+            false => 0.0,
+        };
+        line.push(BARS[level as usize]);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sparkline;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0], 100.0, 3), "▁▅█");
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0], 100.0, 5), "  ▁▅█");
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0], 100.0, 2), "▅█");
+        assert_eq!(sparkline(&[10.0, 500.0], 100.0, 2), "▂█");
+        assert_eq!(sparkline(&[0.0], 0.0, 1), "▁");
+    }
+}
@@ -1,29 +1,40 @@
+use std::collections::HashSet;
+
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::TableState;
 
-#[derive(Clone)]
-pub enum ProcessesSortBy {
-    CPU,
-    MEM,
-}
+use crate::config::process_table::ProcessColumn;
 
-impl ProcessesSortBy {
-    fn default() -> ProcessesSortBy {
-        Self::CPU
-    }
-}
+const DEFAULT_SORT_BY: ProcessColumn = ProcessColumn::Cpu;
 
 pub struct ProcessTableState {
-    pub sort_by: ProcessesSortBy,
+    pub sort_by: ProcessColumn,
     pub show_threads: bool,
+    pub show_io: bool,
+    // pids pinned to the top of the table from the UI
+    pub pinned: HashSet<u32>,
+    // pids whose threads are hidden while threads are shown
+    pub collapsed: HashSet<u32>,
     pub ratatui_table_state: TableState,
+    // where the table and its header cells were last rendered, and how many
+    // rows it had, to find what the mouse clicks on
+    pub area: Rect,
+    pub header_cells: Vec<(Rect, ProcessColumn)>,
+    pub row_count: usize,
 }
 
 impl Default for ProcessTableState {
     fn default() -> ProcessTableState {
         ProcessTableState {
-            sort_by: ProcessesSortBy::default(),
+            sort_by: DEFAULT_SORT_BY,
             show_threads: false,
+            show_io: false,
+            pinned: HashSet::new(),
+            collapsed: HashSet::new(),
             ratatui_table_state: TableState::default(),
+            area: Rect::default(),
+            header_cells: Vec::new(),
+            row_count: 0,
         }
     }
 }
@@ -69,14 +80,64 @@ impl ProcessTableState {
         self.ratatui_table_state.selected()
     }
 
-    pub fn toggle_sort_by(&mut self) {
-        self.sort_by = match self.sort_by {
-            ProcessesSortBy::CPU => ProcessesSortBy::MEM,
-            ProcessesSortBy::MEM => ProcessesSortBy::CPU,
+    /// The column whose header is at `position`, if any
+    pub fn column_at(&self, position: Position) -> Option<ProcessColumn> {
+        self.header_cells
+            .iter()
+            .find(|(rect, _)| rect.contains(position))
+            .map(|(_, column)| *column)
+    }
+
+    /// The index of the row at `position`, taking the scrolling into account
+    pub fn row_at(&self, position: Position) -> Option<usize> {
+        // the first line is the header
+        if !self.area.contains(position) || position.y == self.area.y {
+            return None;
         }
+        let row = self.ratatui_table_state.offset() + (position.y - self.area.y - 1) as usize;
+        (row < self.row_count).then_some(row)
+    }
+
+    /// Sorts by the next column of `columns`, wrapping around
+    pub fn toggle_sort_by(&mut self, columns: &[ProcessColumn]) {
+        let next = columns
+            .iter()
+            .position(|c| *c == self.sort_by)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        self.sort_by = columns
+            .get(next)
+            .or(columns.first())
+            .copied()
+            .unwrap_or(DEFAULT_SORT_BY);
+    }
+
+    pub fn toggle_show_io(&mut self) {
+        self.show_io = !self.show_io;
     }
 
     pub fn toggle_show_threads(&mut self) {
         self.show_threads = !self.show_threads;
     }
+
+    /// Pins or unpins `pid`, returning whether it's pinned now
+    pub fn toggle_pin(&mut self, pid: u32) -> bool {
+        if !self.pinned.remove(&pid) {
+            self.pinned.insert(pid);
+            return true;
+        }
+        false
+    }
+
+    pub fn toggle_collapsed(&mut self, pid: u32) {
+        if !self.collapsed.remove(&pid) {
+            self.collapsed.insert(pid);
+        }
+    }
+
+    /// Forgets the pins and collapsed threads of processes that exited
+    pub fn retain_alive(&mut self, alive: &HashSet<u32>) {
+        self.pinned.retain(|pid| alive.contains(pid));
+        self.collapsed.retain(|pid| alive.contains(pid));
+    }
 }
@@ -0,0 +1,75 @@
+use crate::config::Theme;
+use crate::constants::BYTES_PER_MB;
+use crate::data::processes::ProcessesSnapshot;
+use crate::data::users::{summarize_by_user, UserSummary};
+use ratatui::widgets::Widget;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Rect},
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Cell, Row, Table},
+};
+
+const CONSTRAINTS: [Constraint; 5] = [
+    Constraint::Length(12),
+    Constraint::Length(6),
+    Constraint::Length(8),
+    Constraint::Length(10),
+    Constraint::Length(10),
+];
+
+pub struct UserSummaryWidget<'a> {
+    pub data: &'a ProcessesSnapshot,
+    pub theme: &'a Theme,
+}
+
+impl<'a> Widget for UserSummaryWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header_style = Style::default()
+            .fg(self.theme.processes_header_fg)
+            .bg(self.theme.processes_header_bg);
+        let header = ["USER", " PROCS", "  ▽CPU%", "       RSS", "   GPU MEM"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style)
+            .height(1);
+
+        let rows: Vec<Row> = summarize_by_user(&self.data.processes)
+            .iter()
+            .map(|s| self.create_row(s))
+            .collect();
+
+        Widget::render(Table::new(rows, CONSTRAINTS).header(header), area, buf);
+    }
+}
+
+impl<'a> UserSummaryWidget<'a> {
+    fn create_row(&self, data: &UserSummary) -> Row<'static> {
+        Row::new(vec![
+            Cell::from(Text::from(data.user.clone())),
+            Cell::from(Text::from(data.processes.to_string()).alignment(Alignment::Right)),
+            Cell::from(
+                Line::from(vec![
+                    Span::raw(format!("{:.1}", data.cpu_usage)),
+                    Span::styled("%", Style::default().fg(Color::DarkGray)),
+                ])
+                .alignment(Alignment::Right),
+            ),
+            mib_cell(data.memory),
+            mib_cell(data.gpu_memory),
+        ])
+        .style(Style::default().fg(self.theme.processes_cpu))
+    }
+}
+
+fn mib_cell(bytes: u64) -> Cell<'static> {
+    Cell::from(
+        Line::from(vec![
+            Span::from(format!("{:.0}", bytes / BYTES_PER_MB)),
+            Span::styled("MiB", Style::default().fg(Color::DarkGray)),
+        ])
+        .alignment(Alignment::Right),
+    )
+}
@@ -1,21 +1,43 @@
+use crossterm::event::{KeyCode, KeyModifiers};
 use mltop::config::init_config;
 use mltop::constants::BYTES_PER_GB;
-use mltop::data::cpu::{CoreSnapshot, CpuSnapshot};
-use mltop::data::gpu::GpuSnapshot;
-use mltop::data::memory::MemorySnapshot;
-use mltop::data::processes::ProcessesSnapshot;
+use mltop::data::cgroup_limits::CgroupLimits;
+use mltop::data::cpu::{CoreSnapshot, CpuBreakdown, CpuSnapshot};
+use mltop::data::disk::{DiskSnapshot, DisksSnapshot};
+use mltop::data::filesystem::{FilesystemSnapshot, FilesystemsSnapshot};
+use mltop::data::gpu::{GpuDetails, GpuSnapshot};
+use mltop::data::host::{HostSnapshot, Pressure};
+use mltop::data::memory::{MemoryDetails, MemorySnapshot};
+use mltop::data::network::{InterfaceSnapshot, NetworkSnapshot};
+use mltop::data::numa::{NumaNode, NumaSnapshot};
+use mltop::data::power::PowerSnapshot;
+use mltop::data::processes::{Process, ProcessType, ProcessesSnapshot};
+use mltop::data::sensors::{SensorSnapshot, SensorsSnapshot};
 use mltop::system::FakeSystem;
 use mltop::tui::Tui;
 use ratatui::backend::TestBackend;
+use std::time::{Duration, Instant};
 
 fn cpu() -> CpuSnapshot {
+    let breakdown = CpuBreakdown {
+        user: 0.3,
+        system: 0.1,
+        iowait: 0.1,
+        ..CpuBreakdown::default()
+    };
     let cores: Vec<CoreSnapshot> = (0..32)
         .map(|_| CoreSnapshot {
             usage: 0.5,
             temp: 50.0,
+            frequency: 3400,
+            breakdown,
         })
         .collect();
-    CpuSnapshot { usage: 0.5, cores }
+    CpuSnapshot {
+        usage: 0.5,
+        cores,
+        breakdown,
+    }
 }
 
 fn memory() -> MemorySnapshot {
@@ -24,6 +46,7 @@ fn memory() -> MemorySnapshot {
         total: 64 * BYTES_PER_GB, // 64 GB
         used_swap: 0,
         total_swap: 64 * BYTES_PER_GB, // 64 GB
+        details: MemoryDetails::default(),
     }
 }
 
@@ -37,6 +60,141 @@ fn gpu() -> GpuSnapshot {
         max_power: 500,
         power_usage: 250,
         fan_speed: Some(50),
+        numa_node: Some(1),
+        details: GpuDetails::default(),
+    }
+}
+
+fn power() -> PowerSnapshot {
+    PowerSnapshot {
+        cpu: Some(120.0),
+        dram: Some(15.0),
+        gpu: Some(250.0),
+        energy_wh: 1.5,
+    }
+}
+
+fn disks() -> DisksSnapshot {
+    DisksSnapshot {
+        disks: vec![DiskSnapshot {
+            name: "nvme0n1".to_string(),
+            read_rate: 2 * BYTES_PER_GB,
+            write_rate: 50 * 1024 * 1024,
+            read_iops: 16000.0,
+            write_iops: 400.0,
+            utilization: 85.0,
+        }],
+    }
+}
+
+fn network() -> NetworkSnapshot {
+    NetworkSnapshot {
+        interfaces: vec![
+            InterfaceSnapshot {
+                name: "eth0".to_string(),
+                rx_rate: 300 * 1024 * 1024,
+                tx_rate: 20 * 1024 * 1024,
+                speed: Some(25_000_000_000 / 8),
+            },
+            InterfaceSnapshot {
+                name: "mlx5_0:1".to_string(),
+                rx_rate: 9 * BYTES_PER_GB,
+                tx_rate: 9 * BYTES_PER_GB,
+                speed: Some(200_000_000_000 / 8),
+            },
+        ],
+    }
+}
+
+fn filesystems() -> FilesystemsSnapshot {
+    FilesystemsSnapshot {
+        filesystems: vec![FilesystemSnapshot {
+            path: "/data".to_string(),
+            device: "/dev/nvme1n1".to_string(),
+            used: 3500 * BYTES_PER_GB,
+            total: 3800 * BYTES_PER_GB,
+        }],
+    }
+}
+
+fn host() -> HostSnapshot {
+    HostSnapshot {
+        hostname: "gpu-node-01".to_string(),
+        kernel: "6.8.0-45-generic".to_string(),
+        uptime: 3 * 86400 + 4 * 3600,
+        load_average: [63.5, 60.2, 58.9],
+        pressure: Pressure {
+            cpu: Some(12.5),
+            memory: Some(0.0),
+            io: None,
+        },
+    }
+}
+
+fn numa() -> NumaSnapshot {
+    let node = |id: usize| NumaNode {
+        id,
+        cpus: (id * 16..(id + 1) * 16).collect(),
+        memory_total: 64 * BYTES_PER_GB,
+        memory_used: 20 * BYTES_PER_GB,
+    };
+    NumaSnapshot {
+        nodes: vec![node(0), node(1)],
+        gpu_nodes: vec![Some(1)],
+    }
+}
+
+fn sensors() -> SensorsSnapshot {
+    SensorsSnapshot {
+        sensors: vec![
+            SensorSnapshot {
+                label: "coretemp Package id 0".to_string(),
+                temperature: Some(71.0),
+                max: Some(88.0),
+                critical: Some(100.0),
+            },
+            SensorSnapshot {
+                label: "nvme Composite Samsung SSD 990 PRO 2TB".to_string(),
+                temperature: Some(79.0),
+                max: Some(82.0),
+                critical: Some(84.8),
+            },
+        ],
+    }
+}
+
+fn cgroup() -> CgroupLimits {
+    CgroupLimits {
+        memory_max: Some(64 * BYTES_PER_GB),
+        memory_used: 56 * BYTES_PER_GB,
+        cpu_quota: Some(6.0),
+        cpuset: Some(vec![0, 1, 2, 3, 16, 17, 18, 19]),
+        cpu_usage: Some(4.2),
+    }
+}
+
+fn process(pid: u32, type_: ProcessType, command: &str) -> Process {
+    Process {
+        pid,
+        type_,
+        user: String::from("alice"),
+        command: String::from(command),
+        cpu_usage: 50.0,
+        memory: 2 * BYTES_PER_GB,
+        memory_usage: 3.1,
+        gpu_memory: 4 * BYTES_PER_GB,
+        gpus: vec![0],
+        nice: 0,
+        affinity: (0..32).collect(),
+        last_cpu: Some(3),
+        read_rate: 1024,
+        write_rate: 0,
+        state: 'R',
+        start_time: 0,
+        threads: 12,
+        framework: None,
+        container: None,
+        owner: None,
     }
 }
 
@@ -46,12 +204,33 @@ fn processes() -> ProcessesSnapshot {
     }
 }
 
+fn training_processes() -> ProcessesSnapshot {
+    ProcessesSnapshot {
+        processes: vec![
+            process(1000, ProcessType::GpuCompute, "/usr/bin/python train.py"),
+            process(1001, ProcessType::Cpu, "/usr/bin/bash"),
+            Process {
+                owner: Some(1000),
+                ..process(1002, ProcessType::UserThread, "/usr/bin/python train.py")
+            },
+        ],
+    }
+}
+
 fn system() -> FakeSystem {
     FakeSystem::default()
         .with_cpu(cpu())
         .with_memory(memory())
         .with_gpu(gpu())
         .with_processes(processes())
+        .with_power(power())
+        .with_disks(disks())
+        .with_network(network())
+        .with_filesystems(filesystems())
+        .with_host(host())
+        .with_numa(numa())
+        .with_sensors(sensors())
+        .with_cgroup(cgroup())
 }
 
 #[test]
@@ -62,3 +241,150 @@ fn test_large_system() {
     let mut app = Tui::fake(system, backend);
     app.render();
 }
+
+#[test]
+fn test_panels() {
+    init_config();
+    let backend = TestBackend::new(160, 80);
+    let mut app = Tui::fake(system(), backend);
+    app.render();
+
+    let text = app.rendered_text();
+    for expected in [
+        "gpu-node-01",
+        "Node 1",
+        "Limit 4.2/6 CPUs",
+        "56.0G/64.0G limit",
+        "Power",
+        "Session: 1.50 Wh",
+        "NUMA 1",
+    ] {
+        assert!(text.contains(expected), "{:?} isn't shown", expected);
+    }
+
+    let panels = [
+        ('d', "nvme0n1"),
+        ('w', "mlx5_0:1"),
+        ('f', "/dev/nvme1n1"),
+        ('s', "Samsung SSD 990 PRO"),
+        ('m', "/dev/shm"),
+    ];
+    for (key, expected) in panels {
+        assert!(!app.rendered_text().contains(expected));
+        app.press(KeyCode::Char(key));
+        let text = app.rendered_text();
+        assert!(
+            text.contains(expected),
+            "{:?} isn't shown after {:?}",
+            expected,
+            key
+        );
+    }
+
+    app.press(KeyCode::Char('h'));
+    assert!(!app.rendered_text().contains("gpu-node-01"));
+}
+
+#[test]
+fn test_training_processes() {
+    init_config();
+    let system = system().with_processes(training_processes());
+    // tall enough for the process table to fit under the NUMA nodes
+    let backend = TestBackend::new(160, 80);
+    let mut app = Tui::fake(system, backend);
+    app.render();
+    assert!(app.rendered_text().contains("/usr/bin/python train.py"));
+    assert!(!app.rendered_text().contains("1002"));
+
+    app.press(KeyCode::Char('t'));
+    assert!(app.rendered_text().contains("1002"));
+    app.press(KeyCode::Char('i'));
+    assert!(app.rendered_text().contains("WRITE/s"));
+}
+
+#[test]
+fn test_affinity_popup() {
+    init_config();
+    let system = system().with_processes(training_processes());
+    let mut app = Tui::fake(system, TestBackend::new(160, 80));
+    let set_affinity = |app: &mut Tui<FakeSystem, TestBackend>, cpus: &str| {
+        app.press(KeyCode::Down);
+        app.press(KeyCode::Char('a'));
+        // the popup starts with the current affinity, 0-31
+        for _ in 0..4 {
+            app.press(KeyCode::Backspace);
+        }
+        for c in cpus.chars() {
+            app.press(KeyCode::Char(c));
+        }
+        // shortcuts don't end up in the input
+        app.press_with(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        app.press(KeyCode::Enter);
+    };
+
+    set_affinity(&mut app, "0-3");
+    assert!(app.rendered_text().contains("to 0-3"));
+    // the machine has 32 cpus
+    set_affinity(&mut app, "30-33");
+    assert!(app.rendered_text().contains("Invalid CPU list: 30-33"));
+}
+
+#[test]
+fn test_refresh_rate() {
+    init_config();
+    let mut app = Tui::fake(system(), TestBackend::new(160, 80)).with_refresh_rate(100);
+    // too fast for the cpu usage, so it's raised to the minimum
+    assert_eq!(app.refresh_rate_ms(), 250);
+
+    app.press(KeyCode::Char('+'));
+    assert_eq!(app.refresh_rate_ms(), 500);
+    assert!(app.rendered_text().contains("Refresh: 500ms"));
+    app.press_with(KeyCode::Char('+'), KeyModifiers::SHIFT);
+    assert_eq!(app.refresh_rate_ms(), 1000);
+    assert!(app.rendered_text().contains("Refresh: 1s"));
+
+    for _ in 0..10 {
+        app.press(KeyCode::Char('+'));
+    }
+    assert_eq!(app.refresh_rate_ms(), 10_000);
+    assert!(app.rendered_text().contains("Refresh: 10s"));
+    app.press(KeyCode::Char('-'));
+    assert_eq!(app.refresh_rate_ms(), 5000);
+    assert!(app.rendered_text().contains("Refresh: 5s"));
+}
+
+#[test]
+fn test_memory_leak_warning() {
+    init_config();
+    let memory = MemorySnapshot {
+        details: MemoryDetails {
+            available: 8 * BYTES_PER_GB,
+            ..MemoryDetails::default()
+        },
+        ..memory()
+    };
+    let system = system()
+        .with_memory(memory)
+        .with_cgroup(CgroupLimits::default());
+    let mut app = Tui::fake(system, TestBackend::new(160, 80));
+
+    // 10 MB every 10 seconds over the 10 minutes of the leak window
+    let start = Instant::now();
+    for i in 0..=60 {
+        let leaking = Process {
+            memory: BYTES_PER_GB + i * 10_000_000,
+            ..process(1000, ProcessType::GpuCompute, "/usr/bin/python train.py")
+        };
+        app.system_mut().set_processes(ProcessesSnapshot {
+            processes: vec![leaking],
+        });
+        app.update_at(start + Duration::from_secs(i * 10));
+    }
+    app.render();
+    let text = app.rendered_text();
+    assert!(
+        text.contains("Memory keeps growing: python (1000) +3.4G/h, memory runs out in ~2h 23m"),
+        "{}",
+        text
+    );
+}